@@ -1,46 +1,107 @@
 use lab_core::prelude::*;
 use lab_entities::prelude::*;
+use std::collections::HashMap;
 
-/// Super Basic right now, Move all NPCs in the scene every n seconds
+#[derive(Default)]
+pub struct NpcTurnState {
+    pub turn_events: EventReader<TurnEvent>,
+    /// Turns each npc earned but hasn't taken yet
+    pub turns: HashMap<Entity, u32>,
+}
+
+/// Super Basic right now, Move all NPCs in the scene every n seconds, or every
+/// turn they get in turn mode. In grid mode they step to the next tile the
+/// same way the player does.
 pub fn npc_move_system(
     mut commands: Commands,
+    mut state: Local<NpcTurnState>,
     time: Res<Time>,
     settings: Res<WorldSettings>,
+    turn_events: Res<Events<TurnEvent>>,
+    step_query: Query<&GridStep>,
+    behavior_query: Query<&Behavior>,
     mut query: Query<(Entity, &NonPlayer, &mut Timer, &mut Transform)>,
 ) {
-    for (entity, _np, mut timer, trans) in &mut query.iter() {
-        timer.tick(time.delta_seconds);
-        if timer.finished {
+    let state = &mut *state;
+
+    // npcs take one turn a frame, the rest wait until they are free again
+    for turn in state.turn_events.iter(&turn_events) {
+        *state.turns.entry(turn.entity).or_insert(0) += 1;
+    }
+
+    if !settings.turn_mode {
+        state.turns.clear();
+    }
+
+    for entity in query.removed::<NonPlayer>().iter() {
+        state.turns.remove(entity);
+    }
+
+    for (entity, _np, mut timer, mut trans) in &mut query.iter() {
+        // an npc still on its way to the next tile keeps its turns for later
+        if step_query.get::<GridStep>(entity).is_ok() {
+            continue;
+        }
+
+        // some mobs stay where they were put, they spend their turns standing
+        if let Ok(behavior) = behavior_query.get::<Behavior>(entity) {
+            if *behavior == Behavior::Stand {
+                state.turns.remove(&entity);
+                continue;
+            }
+        }
+
+        let acts = if settings.turn_mode {
+            match state.turns.get_mut(&entity) {
+                Some(turns) if *turns > 0 => {
+                    *turns -= 1;
+                    true
+                }
+                _ => false,
+            }
+        } else {
+            timer.tick(time.delta_seconds);
+            timer.finished
+        };
+
+        if acts {
             let old_translation = trans.translation();
-            let direction = rand::random::<CardinalDirection>();
+            let cardinal = rand::random::<CardinalDirection>();
+            let direction = cardinal.vec2().extend(0.);
             let scale = trans.scale().x();
-            match direction {
-                CardinalDirection::West => {
-                    *trans.translation().x_mut() -= settings.base_npc_speed * scale
-                } // replace with npc speed
-                CardinalDirection::North => {
-                    *trans.translation().y_mut() += settings.base_npc_speed * scale
-                }
-                CardinalDirection::South => {
-                    *trans.translation().y_mut() -= settings.base_npc_speed * scale
+
+            let new_translation = match settings.movement() {
+                // replace with npc speed
+                MovementMode::Free => old_translation + direction * settings.base_npc_speed * scale,
+                MovementMode::Grid => {
+                    let tile = settings.tile(scale);
+
+                    TileCoord::from_world(old_translation.truncate(), tile)
+                        .neighbor(cardinal)
+                        .to_world(tile)
+                        .extend(old_translation.z())
                 }
-                CardinalDirection::East => {
-                    *trans.translation().x_mut() += settings.base_npc_speed * scale
+            };
+
+            match settings.movement() {
+                MovementMode::Free => trans.set_translation(new_translation),
+                MovementMode::Grid if direction != Vec3::zero() => {
+                    commands.insert_one(
+                        entity,
+                        GridStep::new(old_translation, new_translation, settings.step_seconds),
+                    );
                 }
-                CardinalDirection::None => {}
+                MovementMode::Grid => {}
             }
 
             commands.insert(
                 entity,
-                (Movement {
-                    start: old_translation,
-                    end: trans.translation(),
-                    direction: old_translation - trans.translation(),
-                    legal: Some(true),
-                },),
+                (Movement::new(old_translation, new_translation, direction),),
             );
 
             timer.reset();
         }
     }
+
+    state.turns.retain(|_, turns| *turns > 0);
 }
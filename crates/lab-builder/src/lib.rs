@@ -1,17 +1,19 @@
 use bevy::prelude::*;
 
 pub mod maps;
+pub mod mobs;
 pub mod systems;
 pub mod text;
 
 use lab_core::prelude::*;
-use lab_entities::player::NonPlayer;
+use lab_entities::prelude::{Behavior, Health, MobLoot, NonPlayer, Stats};
 use lab_sprites::SpriteInfo;
 use systems::*;
 
 pub mod prelude {
     pub use crate::*;
     pub use maps::*;
+    pub use mobs::*;
     pub use systems::*;
     pub use text::*;
 }
@@ -30,6 +32,11 @@ pub struct BuilderPlugin;
 impl Plugin for BuilderPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<BuilderSettings>()
+            .init_resource::<mobs::MobCatalog>()
+            .add_startup_system_to_stage(
+                lab_core::stages::INIT,
+                mobs::load_mob_catalog_system.system(),
+            )
             // system to init the tile palette
             .add_startup_system_to_stage(
                 lab_core::stages::POST_INIT,
@@ -50,6 +57,11 @@ impl Plugin for BuilderPlugin {
 #[derive(Default)]
 pub struct BuilderSettings {
     pub move_mode: bool,
+    /// Clicking two tiles wires the first to the second
+    pub link_mode: bool,
+    pub link_source: Option<Entity>,
+    /// Wires made so far, used to name new channels
+    pub wires: u32,
 }
 
 /// Mark a tile as moving (i.e. being dragged)
@@ -66,7 +78,14 @@ pub struct MobComponents {
     pub timer: Timer,
     pub location: Location,
     pub zoomable: Zoomable,
-    pub interactable_type: InteractableType
+    pub interactable_type: InteractableType,
+    pub speed: Speed,
+    pub stats: Stats,
+    pub health: Health,
+    pub level: Level,
+    pub loot: MobLoot,
+    pub behavior: Behavior,
+    pub interaction: InteractionKey,
 }
 
 impl Clone for MobComponents {
@@ -81,7 +100,14 @@ impl Clone for MobComponents {
             timer: self.timer.clone(),
             location: self.location.clone(),
             zoomable: self.zoomable.clone(),
-            interactable_type: self.interactable_type.clone()
+            interactable_type: self.interactable_type.clone(),
+            speed: self.speed,
+            stats: self.stats.clone(),
+            health: self.health,
+            level: self.level,
+            loot: self.loot.clone(),
+            behavior: self.behavior,
+            interaction: self.interaction.clone(),
         }
     }
 }
@@ -1,7 +1,10 @@
-use crate::*;
+use crate::{mobs::MobCatalog, *};
 use bevy::prelude::*;
 use lab_core::prelude::*;
-use lab_world::{TileComponents, TileInteraction, TilePalette};
+use lab_world::{
+    item_sprite, keys, Concealment, Hazard, InteractionHandlers, Lock, SignalWiring,
+    TileComponents, TilePalette,
+};
 use lab_data::ItemDefinition;
 #[derive(Clone, Debug)]
 pub struct Blueprint {
@@ -35,6 +38,9 @@ pub struct MapBuilder {
     pub tiles: Vec<TileComponents>,
     pub mobs: Vec<MobComponents>,
     pub items: Vec<(TileComponents, ItemDefinition)>,
+    /// Objects spawned as Moveable, the player can push them around
+    pub pushables: Vec<TileComponents>,
+    pub hazards: Vec<(TileComponents, Hazard)>,
     pub blueprints: Vec<Blueprint>,
 }
 
@@ -47,7 +53,9 @@ impl<'a> MapBuilder {
             tiles: Vec::new(),
             blueprints: Vec::new(),
             mobs: Vec::new(),
-            items: Vec::new()
+            items: Vec::new(),
+            pushables: Vec::new(),
+            hazards: Vec::new(),
         }
     }
     pub fn reset_position(&mut self) -> &MapBuilder {
@@ -66,7 +74,7 @@ impl<'a> MapBuilder {
         pos: RelativePosition,
         count: u32,
         tile_name: String,
-        interaction: WorldHandle<TileInteraction>,
+        interaction: &str,
         descripton: ItemDefinition) -> &mut Self {
             let comps = self
                 .world_catalog
@@ -82,7 +90,7 @@ impl<'a> MapBuilder {
                     Vec2::new(comps.sprite.width as f32, comps.sprite.height as f32),
                 );
 
-                my_comp.interaction = interaction;
+                my_comp.interaction = InteractionKey::new(interaction);
 
                 self.current_location = my_comp.location;
 
@@ -92,6 +100,85 @@ impl<'a> MapBuilder {
             self
 
     }
+
+    /// Add an item from the catalog, drawn as the tile named by its sprite
+    pub fn add_catalog_item(
+        &mut self,
+        pos: RelativePosition,
+        count: u32,
+        catalog: &ItemCatalog,
+        id: &str,
+    ) -> &mut Self {
+        let definition = match catalog.get(id) {
+            Some(definition) => definition.clone(),
+            None => {
+                log::warn!("Cannot find item {} in the catalog, it is left out", id);
+                return self;
+            }
+        };
+
+        self.add_item(
+            pos,
+            count,
+            item_sprite(&definition).to_string(),
+            keys::ITEM_PICKUP,
+            definition,
+        )
+    }
+
+    pub fn add_pushable(&mut self, pos: RelativePosition, count: u32, tile_name: String) -> &mut Self {
+        let comps = self
+            .world_catalog
+            .components
+            .get(&tile_name)
+            .expect("Cannot find tiles");
+
+        for _ in 0..count {
+            let mut my_comp = comps.clone();
+
+            my_comp.location = self.get_location(
+                pos,
+                Vec2::new(comps.sprite.width as f32, comps.sprite.height as f32),
+            );
+
+            my_comp.interaction = InteractionKey::new(keys::OBJECT_PUSH);
+
+            self.current_location = my_comp.location;
+
+            self.pushables.push(my_comp);
+        }
+
+        self
+    }
+
+    pub fn add_hazard(
+        &mut self,
+        pos: RelativePosition,
+        count: u32,
+        tile_name: String,
+        hazard: Hazard,
+    ) -> &mut Self {
+        let comps = self
+            .world_catalog
+            .components
+            .get(&tile_name)
+            .expect("Cannot find tiles");
+
+        for _ in 0..count {
+            let mut my_comp = comps.clone();
+
+            my_comp.location = self.get_location(
+                pos,
+                Vec2::new(comps.sprite.width as f32, comps.sprite.height as f32),
+            );
+
+            self.current_location = my_comp.location;
+
+            self.hazards.push((my_comp, hazard.clone()));
+        }
+
+        self
+    }
     pub fn to_blueprint(&mut self, name: &str) -> Blueprint {
         let bp = Blueprint {
             name: name.to_string(),
@@ -152,12 +239,11 @@ impl<'a> MapBuilder {
                     let mut comp = comps.clone();
 
                     comp.name = Named(tile_name.to_string());
-                    comp.location = Location(
-                        loc.0 + (x * comp.sprite.width) as f32,
-                        loc.1 - (y * comp.sprite.height) as f32,
-                        loc.2,
-                        comp.location.3,
-                    );
+                    let tile = comp.sprite.size();
+
+                    comp.location = TileCoord::from_location(loc, tile)
+                        .offset(x as i32, -(y as i32))
+                        .to_location(tile, loc.2, comp.location.3);
                     println!("Location: {:?}", comp.location);
                     self.tiles.push(comp);
                 }
@@ -172,21 +258,17 @@ impl<'a> MapBuilder {
     fn get_location(&self, pos: RelativePosition, tile_size: Vec2) -> Location {
         let loc = self.current_location;
 
-        match pos {
-            RelativePosition::LeftOf => {
-                Location(loc.0 - tile_size.x(), loc.1, loc.2, WorldLocation::World)
-            }
-            RelativePosition::RightOf => {
-                Location(loc.0 + tile_size.x(), loc.1, loc.2, WorldLocation::World)
-            }
-            RelativePosition::Above => {
-                Location(loc.0, loc.1 + tile_size.y(), loc.2, WorldLocation::World)
-            }
-            RelativePosition::Below => {
-                Location(loc.0, loc.1 - tile_size.y(), loc.2, WorldLocation::World)
-            }
-            _ => self.current_location,
-        }
+        let direction = match pos {
+            RelativePosition::LeftOf => CardinalDirection::West,
+            RelativePosition::RightOf => CardinalDirection::East,
+            RelativePosition::Above => CardinalDirection::North,
+            RelativePosition::Below => CardinalDirection::South,
+            _ => return self.current_location,
+        };
+
+        TileCoord::from_location(&loc, tile_size)
+            .neighbor(direction)
+            .to_location(tile_size, loc.2, WorldLocation::World)
     }
 
     pub fn add_interactable(
@@ -194,7 +276,7 @@ impl<'a> MapBuilder {
         pos: RelativePosition,
         count: u32,
         tile_name: String,
-        interaction: WorldHandle<TileInteraction>,
+        interaction: &str,
     ) -> &mut Self {
         let comps = self
             .world_catalog
@@ -210,7 +292,7 @@ impl<'a> MapBuilder {
                 Vec2::new(comps.sprite.width as f32, comps.sprite.height as f32),
             );
 
-            my_comp.interaction = interaction;
+            my_comp.interaction = InteractionKey::new(interaction);
 
             self.current_location = my_comp.location;
 
@@ -218,34 +300,49 @@ impl<'a> MapBuilder {
         }
         self
     }
+    /// Give the tile added last its own interaction handlers
+    pub fn with_handlers(&mut self, handlers: InteractionHandlers) -> &mut Self {
+        if let Some(tile) = self.tiles.last_mut() {
+            tile.handlers = handlers;
+        }
+
+        self
+    }
+
+    /// Wire the tile added last to others, see SignalWiring
+    pub fn with_wiring(&mut self, wiring: SignalWiring) -> &mut Self {
+        if let Some(tile) = self.tiles.last_mut() {
+            tile.wiring = wiring;
+        }
+
+        self
+    }
+
+    /// Hide the tile added last behind a disguise, see Concealment
+    pub fn with_concealment(&mut self, concealment: Concealment) -> &mut Self {
+        if let Some(tile) = self.tiles.last_mut() {
+            tile.concealment = concealment;
+        }
+
+        self
+    }
+
+    /// Put a lock on the tile added last
+    pub fn with_lock(&mut self, lock: Lock) -> &mut Self {
+        if let Some(tile) = self.tiles.last_mut() {
+            tile.lock = lock;
+        }
+
+        self
+    }
+
     pub fn add_tiles(&mut self, pos: RelativePosition, count: u32, tile_name: String) -> &mut Self {
         if let Some(comps) = self.world_catalog.components.get(&tile_name) {
             for _ in 0..count {
                 let mut my_comp = comps.clone();
                 my_comp.name = Named(tile_name.to_string());
 
-                let loc = self.current_location;
-
-                let tile_size_x = comps.sprite.width as f32;
-                let tile_size_y = comps.sprite.height as f32;
-
-                //println!("Tile Size: {},{}", tile_size_x, tile_size_y);
-
-                let location = match pos {
-                    RelativePosition::LeftOf => {
-                        Location(loc.0 - tile_size_x, loc.1, loc.2, WorldLocation::World)
-                    }
-                    RelativePosition::RightOf => {
-                        Location(loc.0 + tile_size_x, loc.1, loc.2, WorldLocation::World)
-                    }
-                    RelativePosition::Above => {
-                        Location(loc.0, loc.1 + tile_size_y, loc.2, WorldLocation::World)
-                    }
-                    RelativePosition::Below => {
-                        Location(loc.0, loc.1 - tile_size_y, loc.2, WorldLocation::World)
-                    }
-                    _ => self.current_location,
-                };
+                let location = self.get_location(pos, comps.sprite.size());
 
                 println!(
                     "Adding tile at {:?} last location: {:?}",
@@ -263,22 +360,44 @@ impl<'a> MapBuilder {
         self
     }
 
-    pub fn add_mobs(&mut self, mut pos: Location, count: u32, mob_name: String) -> &mut Self {
-        if let Some(comps) = self.world_catalog.components.get(&mob_name) {
-            for instance_num in 0..count {
-                // modify z index because right now sprites are clipping if z = other_z
-                pos.2 += instance_num as f32;
-
-                self.mobs.push(MobComponents {
-                    named: Named(mob_name.clone().into()),
-                    sprite: comps.sprite.clone(),
-                    location: pos,
-                    timer: Timer::from_seconds(2.0, false),
-                    interactable_type: InteractableType::Npc,
-                    ..Default::default()
-                });
+    /// Add mobs from the catalog, drawn as the tile named by their sprite
+    pub fn add_mobs(
+        &mut self,
+        mut pos: Location,
+        count: u32,
+        catalog: &MobCatalog,
+        id: &str,
+    ) -> &mut Self {
+        let definition = match catalog.get(id) {
+            Some(definition) => definition,
+            None => {
+                log::warn!("Cannot find mob {} in the catalog, it is left out", id);
+                return self;
+            }
+        };
+        let comps = match self.world_catalog.components.get(&definition.sprite) {
+            Some(comps) => comps,
+            None => {
+                log::warn!(
+                    "Cannot find tile {} for mob {}, it is left out",
+                    definition.sprite,
+                    id
+                );
+                return self;
             }
+        };
+
+        for instance_num in 0..count {
+            // modify z index because right now sprites are clipping if z = other_z
+            pos.2 += instance_num as f32;
+
+            self.mobs.push(MobComponents::from_definition(
+                definition,
+                comps.sprite.clone(),
+                pos,
+            ));
         }
+
         self
     }
 
@@ -286,3 +405,46 @@ impl<'a> MapBuilder {
         self.tiles.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lab_data::MobDefinition;
+    use lab_sprites::SpriteInfo;
+
+    #[test]
+    fn mobs_missing_from_the_content_are_left_out() {
+        let mut palette = TilePalette::default();
+        palette.components.insert(
+            "mob_1".into(),
+            TileComponents {
+                sprite: SpriteInfo {
+                    name: "mob_1".into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let mut catalog = MobCatalog::default();
+        for (id, sprite) in [("brewer", "mob_1"), ("ghost", "mob_404")].iter() {
+            catalog.add(MobDefinition {
+                id: id.to_string(),
+                name: id.to_string(),
+                sprite: sprite.to_string(),
+                ..Default::default()
+            });
+        }
+
+        let mut builder = MapBuilder::new(palette, &Location::default());
+        let at = Location::default();
+
+        builder
+            .add_mobs(at, 1, &catalog, "cellar_rat")
+            .add_mobs(at, 1, &catalog, "ghost");
+        assert!(builder.mobs.is_empty());
+
+        builder.add_mobs(at, 2, &catalog, "brewer");
+        assert_eq!(builder.mobs.len(), 2);
+    }
+}
@@ -0,0 +1,60 @@
+/// Module for the mobs of the game
+///
+/// Mobs are content, every json, ron or toml file in MOBS_PATH holds
+/// MobDefinitions and is loaded into the MobCatalog at startup. A definition
+/// says everything about the mob but where it is, the MapBuilder places them.
+use crate::MobComponents;
+use bevy::prelude::*;
+use lab_core::prelude::*;
+use lab_data::{Catalog, MobDefinition};
+use lab_entities::prelude::*;
+use lab_sprites::SpriteInfo;
+
+/// Folder the mob definitions are read from
+pub const MOBS_PATH: &str = "resources/mobs";
+
+/// Seconds between the moves of a mob that doesn't say otherwise
+pub const MOVE_SECONDS: f32 = 2.;
+
+/// Resource holding every mob definition by id
+pub type MobCatalog = Catalog<MobDefinition>;
+
+impl MobComponents {
+    /// The mob the definition describes, drawn with the sprite at the location
+    pub fn from_definition(
+        definition: &MobDefinition,
+        sprite: SpriteInfo,
+        location: Location,
+    ) -> MobComponents {
+        let stats = Stats::from_attributes(&definition.attributes);
+
+        MobComponents {
+            named: Named(definition.name.clone()),
+            sprite,
+            location,
+            health: Health::from_stats(&stats),
+            stats,
+            loot: MobLoot(definition.inventory.clone()),
+            behavior: definition.behavior,
+            interaction: InteractionKey::new(&definition.interaction),
+            timer: Timer::from_seconds(definition.move_seconds.unwrap_or(MOVE_SECONDS), false),
+            speed: definition.speed.map(Speed).unwrap_or_default(),
+            interactable_type: InteractableType::Npc,
+            ..Default::default()
+        }
+    }
+}
+
+/// Spawn the mob along with its sprite, its loot is handed out once it is in
+/// the world
+pub fn spawn_mob(commands: &mut Commands, mob: MobComponents) -> &mut Commands {
+    let sprite = mob.sprite.to_components(mob.location.into(), 1.);
+
+    commands.spawn(mob).with_bundle(sprite)
+}
+
+pub fn load_mob_catalog_system(mut catalog: ResMut<MobCatalog>) {
+    catalog.load(MOBS_PATH);
+
+    log::info!("Loaded {} mob definitions", catalog.len());
+}
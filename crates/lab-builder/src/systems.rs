@@ -1,7 +1,7 @@
 use bevy::{prelude::*, render::camera::Camera};
 
 use crate::{BuilderSettings, MovingTile};
-use lab_core::prelude::{Location, WorldLocation};
+use lab_core::prelude::{InteractionKey, Location, SpatialIndex, WorldLocation};
 use lab_input::{Mouse, MouseClickEvent, MouseState, ScrollState, SelectedTile};
 use lab_sprites::*;
 use lab_world::*;
@@ -68,15 +68,17 @@ pub fn update_tile_system(
 
 pub fn add_tiles_to_world_system(
     mut commands: Commands,
-    settings: ResMut<BuilderSettings>,
+    mut settings: ResMut<BuilderSettings>,
     selected_tile: Res<SelectedTile>,
     scroll_state: Res<ScrollState>,
     palette: Res<TilePalette>,
     mouse: ResMut<Mouse>,
     mouse_events: ResMut<Events<MouseClickEvent>>,
     mut mouse_click: ResMut<MouseState>,
-    mut interaction_query: Query<(Entity, &SpriteInfo, &mut Transform, &Draw)>,
+    index: Res<SpatialIndex>,
+    interaction_query: Query<(Entity, &SpriteInfo, &mut Transform, &Draw)>,
     mut moving_tile_query: Query<(Entity, &MovingTile, &mut Transform)>,
+    mut wiring_query: Query<(&mut SignalWiring, &mut InteractionKey)>,
 ) {
     for clicks in &mut mouse_click.click_events.iter(&mouse_events) {
         match clicks.button {
@@ -95,35 +97,38 @@ pub fn add_tiles_to_world_system(
                     return;
                 }
 
+                if settings.link_mode {
+                    let picked = top_most(&index, &interaction_query, mouse.position);
+
+                    match (settings.link_source, picked) {
+                        (None, Some(source)) => {
+                            log::info!("Linking from {:?}, click the tile to wire it to", source);
+                            settings.link_source = Some(source);
+                        }
+                        (Some(source), Some(target)) if source != target => {
+                            settings.link_source = None;
+                            link_tiles(&mut settings, &mut wiring_query, source, target);
+                        }
+                        _ => settings.link_source = None,
+                    }
+
+                    return;
+                }
+
                 if settings.move_mode {
-                    for (entity, si, t, _d) in &mut interaction_query.iter() {
-                        let true_location = mouse.position;
-
-                        let scale = t.scale().x();
-                        let translation = t.translation();
-
-                        let (x1, y1) = (
-                            translation.x() - (si.width / 2) as f32 * scale,
-                            translation.y() - (si.height / 2) as f32 * scale,
-                        );
-                        let (x2, y2) = (
-                            translation.x() + ((si.width / 2) as f32 * scale),
-                            translation.y() + ((si.height / 2) as f32 * scale),
-                        );
-
-                        //println!("mouse click: {:?} tile location: ({:?},{:?}) ({:?},{:?})",mouse.position, x1,y1,x2,y2);
-
-                        if true_location.x() >= x1
-                            && true_location.x() <= x2
-                            && true_location.y() >= y1
-                            && true_location.y() <= y2
-                        {
-                            println!("Click on sprite {} {},{}", si.name, true_location.x(), true_location.y());
+                    let true_location = mouse.position;
 
-                            commands.insert_one(entity, MovingTile);
+                    // pick the top most sprite under the mouse
+                    let picked = top_most(&index, &interaction_query, true_location);
 
-                            return;
+                    if let Some(entity) = picked {
+                        if let Ok(si) = interaction_query.get::<SpriteInfo>(entity) {
+                            println!("Click on sprite {} {},{}", si.name, true_location.x(), true_location.y());
                         }
+
+                        commands.insert_one(entity, MovingTile);
+
+                        return;
                     }
                 }
 
@@ -132,6 +137,11 @@ pub fn add_tiles_to_world_system(
 
                 clone.location = Location(x, y, st.level, WorldLocation::World);
 
+                // the selected tile decides how tough the placed tile is
+                if st.hit_points > 0 {
+                    set_durability(&mut clone.state, st.hit_points as i32, st.hardness as i32);
+                }
+
                 commands
                     .spawn(
                         sprite.to_components(Vec3::new(x, y, st.level), scroll_state.current_scale),
@@ -146,6 +156,65 @@ pub fn add_tiles_to_world_system(
 }
 pub struct FreeTile;
 
+/// The top most sprite at the point
+fn top_most(
+    index: &SpatialIndex,
+    query: &Query<(Entity, &SpriteInfo, &mut Transform, &Draw)>,
+    point: Vec2,
+) -> Option<Entity> {
+    index
+        .query_point(point)
+        .into_iter()
+        .filter_map(|entity| {
+            let t = query.get::<Transform>(entity).ok()?;
+
+            Some((entity, t.translation().z()))
+        })
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(entity, _z)| entity)
+}
+
+/// Wire the source to the target, a source keeps sending on its first channel.
+/// Tiles without an interaction of their own become a lever and a gate.
+fn link_tiles(
+    settings: &mut BuilderSettings,
+    wiring_query: &mut Query<(&mut SignalWiring, &mut InteractionKey)>,
+    source: Entity,
+    target: Entity,
+) {
+    let channel = match wiring_query.get_mut::<SignalWiring>(source) {
+        Ok(mut wiring) => {
+            if wiring.emits.is_empty() {
+                settings.wires += 1;
+                wiring.emits.push(format!("wire_{}", settings.wires));
+            }
+
+            if wiring.kind.is_none() {
+                wiring.kind = Some(SignalKind::Lever);
+            }
+
+            wiring.emits[0].clone()
+        }
+        Err(_) => return,
+    };
+
+    if let Ok(mut wiring) = wiring_query.get_mut::<SignalWiring>(target) {
+        if !wiring.listens.contains(&channel) {
+            wiring.listens.push(channel.clone());
+        }
+    }
+
+    for (entity, key) in [(source, keys::SIGNAL_SWITCH), (target, keys::SIGNAL_GATE)].iter() {
+        if let Ok(mut interaction) = wiring_query.get_mut::<InteractionKey>(*entity) {
+            if interaction.is_none() || interaction.0 == keys::NONE {
+                *interaction = InteractionKey::new(key);
+            }
+        }
+    }
+
+    log::info!("Linked {:?} to {:?} on {}", source, target, channel);
+}
+
 pub fn builder_settings_system(
     mut settings: ResMut<BuilderSettings>,
     keyboard_input: Res<Input<KeyCode>>,
@@ -153,6 +222,11 @@ pub fn builder_settings_system(
     if keyboard_input.just_pressed(KeyCode::M) {
         (*settings).move_mode = settings.move_mode == false;
     }
+    if keyboard_input.just_pressed(KeyCode::K) {
+        settings.link_mode = !settings.link_mode;
+        settings.link_source = None;
+        log::info!("Link mode: {}", settings.link_mode);
+    }
 }
 
 pub fn builder_keyboard_system(
@@ -234,6 +308,20 @@ pub fn builder_keyboard_system(
             (camera_offset_x, camera_offset_y),
         );
     }
+    let selection_keys = [
+        KeyCode::Apostrophe,
+        KeyCode::Semicolon,
+        KeyCode::RBracket,
+        KeyCode::LBracket,
+    ];
+
+    if selection_keys
+        .iter()
+        .any(|key| keyboard_input.just_pressed(*key))
+    {
+        select_durability(&mut selected_tile, &palette);
+    }
+
     if keyboard_input.just_pressed(KeyCode::Add) {
         selected_tile.level += 1.;
         println!("Level changed to {}", selected_tile.level.clone());
@@ -244,6 +332,18 @@ pub fn builder_keyboard_system(
     }
 }
 
+/// Start from the hit points and hardness the tile has in the palette
+fn select_durability(selected_tile: &mut SelectedTile, palette: &TilePalette) {
+    let (hit_points, hardness) = palette
+        .items_in_category(&selected_tile.category)
+        .get(selected_tile.tile)
+        .and_then(|tile| durability(&tile.state))
+        .unwrap_or((0, 0));
+
+    selected_tile.hit_points = hit_points.max(0) as u32;
+    selected_tile.hardness = hardness as f32;
+}
+
 fn change_selected_sprite(
     commands: &mut Commands,
     change: i32,
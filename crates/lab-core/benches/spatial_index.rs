@@ -0,0 +1,109 @@
+/// Compares the all-pairs collision scan against the spatial index
+///
+/// Run with `cargo bench -p lab-core --bench spatial_index`
+use bevy::math::Vec2;
+use lab_core::{Bounds, SpatialGrid};
+use std::time::{Duration, Instant};
+
+const TILE_SIZE: f32 = 16.;
+const TILES_PER_ROW: u32 = 100;
+const TILE_COUNT: u32 = 10_000;
+const MOVERS: u32 = 100;
+const FRAMES: u32 = 100;
+
+fn tiles() -> Vec<(u32, Bounds)> {
+    (0..TILE_COUNT)
+        .map(|i| {
+            let center = Vec2::new(
+                (i % TILES_PER_ROW) as f32 * TILE_SIZE,
+                (i / TILES_PER_ROW) as f32 * TILE_SIZE,
+            );
+
+            (
+                i,
+                Bounds::from_center(center, Vec2::new(TILE_SIZE, TILE_SIZE)),
+            )
+        })
+        .collect()
+}
+
+/// Movers spread over the map, shifting a little every frame
+fn movers(frame: u32) -> Vec<Bounds> {
+    (0..MOVERS)
+        .map(|i| {
+            let center = Vec2::new(
+                (i * 97 % TILES_PER_ROW) as f32 * TILE_SIZE + frame as f32 * 0.5,
+                (i * 31 % TILES_PER_ROW) as f32 * TILE_SIZE,
+            );
+
+            Bounds::from_center(center, Vec2::new(8., 16.))
+        })
+        .collect()
+}
+
+fn all_pairs(tiles: &[(u32, Bounds)]) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut collisions = 0;
+
+    for frame in 0..FRAMES {
+        for mover in movers(frame).iter() {
+            collisions += tiles
+                .iter()
+                .filter(|(_, tile)| tile.overlaps(mover))
+                .count();
+        }
+    }
+
+    (start.elapsed(), collisions)
+}
+
+fn indexed(index: &SpatialGrid<u32>) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut collisions = 0;
+
+    for frame in 0..FRAMES {
+        for mover in movers(frame).iter() {
+            collisions += index.query_aabb(mover).len();
+        }
+    }
+
+    (start.elapsed(), collisions)
+}
+
+fn main() {
+    let tiles = tiles();
+
+    let build_start = Instant::now();
+    let mut index = SpatialGrid::default();
+
+    for (tile, bounds) in tiles.iter() {
+        index.insert(*tile, *bounds);
+    }
+
+    let build = build_start.elapsed();
+
+    let (scan_time, scan_collisions) = all_pairs(&tiles);
+    let (index_time, index_collisions) = indexed(&index);
+
+    assert_eq!(scan_collisions, index_collisions);
+
+    println!(
+        "{} static tiles, {} movers, {} frames",
+        TILE_COUNT, MOVERS, FRAMES
+    );
+    println!("building the index:  {:?}", build);
+    println!(
+        "all pairs:           {:?} ({:?} per frame)",
+        scan_time,
+        scan_time / FRAMES
+    );
+    println!(
+        "spatial index:       {:?} ({:?} per frame)",
+        index_time,
+        index_time / FRAMES
+    );
+    println!(
+        "speed up:            {:.1}x",
+        scan_time.as_secs_f64() / index_time.as_secs_f64()
+    );
+}
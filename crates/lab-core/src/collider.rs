@@ -0,0 +1,57 @@
+/// Module for the boxes entities collide with
+///
+/// A Collider is a box around the translation of an entity, scaled along with
+/// it. Sprites get one the size of the sprite, movers take insets off the
+/// sides so they fit through doorways and objects from tiled maps are offset
+/// since they start at their top left corner.
+use crate::Bounds;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Collider {
+    pub size: Vec2,
+    /// From the translation to the center of the box, before scaling
+    pub offset: Vec2,
+    /// Taken off each side, horizontally and vertically
+    pub insets: Vec2,
+}
+
+impl Default for Collider {
+    fn default() -> Self {
+        Collider::new(Vec2::zero())
+    }
+}
+
+impl Collider {
+    pub fn new(size: Vec2) -> Collider {
+        Collider {
+            size,
+            offset: Vec2::zero(),
+            insets: Vec2::zero(),
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_insets(mut self, insets: Vec2) -> Self {
+        self.insets = insets;
+        self
+    }
+
+    /// The box as if the entity was at the translation
+    pub fn bounds_at(&self, translation: Vec2, scale: Vec2) -> Bounds {
+        let size = (self.size - self.insets * 2.).max(Vec2::zero());
+
+        Bounds::from_center(translation + self.offset * scale, size * scale)
+    }
+
+    pub fn bounds(&self, transform: &Transform) -> Bounds {
+        self.bounds_at(
+            transform.translation().truncate(),
+            transform.scale().truncate(),
+        )
+    }
+}
@@ -0,0 +1,196 @@
+/// Module for positions on the tile grid
+///
+/// Tiles are centered on their coordinate, the tile at (0, 0) covers half a
+/// tile to each side of the world origin. Converting takes the size of a tile
+/// in the world, see WorldSettings::tile for the size at a zoom level.
+use crate::{CardinalDirection, Location, WorldLocation, WorldSettings};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TileCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl TileCoord {
+    pub fn new(x: i32, y: i32) -> TileCoord {
+        TileCoord { x, y }
+    }
+
+    /// The tile the point is on
+    pub fn from_world(position: Vec2, tile: Vec2) -> TileCoord {
+        TileCoord {
+            x: (position.x() / tile.x()).round() as i32,
+            y: (position.y() / tile.y()).round() as i32,
+        }
+    }
+
+    pub fn from_location(location: &Location, tile: Vec2) -> TileCoord {
+        TileCoord::from_world(Vec2::new(location.0, location.1), tile)
+    }
+
+    /// The center of the tile
+    pub fn to_world(&self, tile: Vec2) -> Vec2 {
+        Vec2::new(self.x as f32 * tile.x(), self.y as f32 * tile.y())
+    }
+
+    pub fn to_location(&self, tile: Vec2, z: f32, world: WorldLocation) -> Location {
+        let position = self.to_world(tile);
+
+        Location(position.x(), position.y(), z, world)
+    }
+
+    /// Snap the point to the center of the tile it is on
+    pub fn snap(position: Vec2, tile: Vec2) -> Vec2 {
+        TileCoord::from_world(position, tile).to_world(tile)
+    }
+
+    pub fn offset(&self, x: i32, y: i32) -> TileCoord {
+        TileCoord::new(self.x + x, self.y + y)
+    }
+
+    /// The tile next to this one, north is up
+    pub fn neighbor(&self, direction: CardinalDirection) -> TileCoord {
+        match direction {
+            CardinalDirection::North => self.offset(0, 1),
+            CardinalDirection::South => self.offset(0, -1),
+            CardinalDirection::East => self.offset(1, 0),
+            CardinalDirection::West => self.offset(-1, 0),
+            CardinalDirection::None => *self,
+        }
+    }
+
+    pub fn neighbors(&self) -> [TileCoord; 4] {
+        [
+            self.neighbor(CardinalDirection::North),
+            self.neighbor(CardinalDirection::East),
+            self.neighbor(CardinalDirection::South),
+            self.neighbor(CardinalDirection::West),
+        ]
+    }
+
+    /// Steps it takes to get there without moving diagonally
+    pub fn manhattan(&self, other: &TileCoord) -> i32 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    /// Tiles between the two when diagonal steps count as one
+    pub fn distance(&self, other: &TileCoord) -> i32 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+
+    /// Every tile on the line to the other one, both ends included
+    pub fn line_to(&self, other: &TileCoord) -> Vec<TileCoord> {
+        let (dx, dy) = ((other.x - self.x).abs(), -(other.y - self.y).abs());
+        let (sx, sy) = ((other.x - self.x).signum(), (other.y - self.y).signum());
+
+        let mut line = Vec::new();
+        let mut current = *self;
+        let mut error = dx + dy;
+
+        loop {
+            line.push(current);
+
+            if current == *other {
+                return line;
+            }
+
+            let doubled = error * 2;
+
+            if doubled >= dy {
+                error += dy;
+                current.x += sx;
+            }
+            if doubled <= dx {
+                error += dx;
+                current.y += sy;
+            }
+        }
+    }
+}
+
+impl WorldSettings {
+    /// Size of a tile in the world at the scale (zoom)
+    pub fn tile(&self, scale: f32) -> Vec2 {
+        Vec2::new(self.tile_size, self.tile_size) * scale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TILE: f32 = 16.;
+
+    fn tile() -> Vec2 {
+        Vec2::new(TILE, TILE)
+    }
+
+    #[test]
+    fn points_belong_to_the_tile_they_are_closest_to() {
+        assert_eq!(
+            TileCoord::from_world(Vec2::new(7., -7.), tile()),
+            TileCoord::new(0, 0)
+        );
+        assert_eq!(
+            TileCoord::from_world(Vec2::new(9., -9.), tile()),
+            TileCoord::new(1, -1)
+        );
+        assert_eq!(
+            TileCoord::from_world(Vec2::new(-40., 33.), tile()),
+            TileCoord::new(-3, 2)
+        );
+    }
+
+    #[test]
+    fn tiles_round_trip_through_the_world() {
+        let coord = TileCoord::new(-4, 7);
+
+        assert_eq!(coord.to_world(tile()), Vec2::new(-64., 112.));
+        assert_eq!(TileCoord::from_world(coord.to_world(tile()), tile()), coord);
+        assert_eq!(
+            TileCoord::from_world(coord.to_world(tile() * 3.), tile() * 3.),
+            coord
+        );
+    }
+
+    #[test]
+    fn neighbors_are_one_step_away() {
+        let coord = TileCoord::new(2, 2);
+
+        for neighbor in coord.neighbors().iter() {
+            assert_eq!(coord.manhattan(neighbor), 1);
+        }
+
+        assert_eq!(
+            coord.neighbor(CardinalDirection::North),
+            TileCoord::new(2, 3)
+        );
+    }
+
+    #[test]
+    fn distance_counts_diagonal_steps_once() {
+        let a = TileCoord::new(0, 0);
+        let b = TileCoord::new(3, -5);
+
+        assert_eq!(a.distance(&b), 5);
+        assert_eq!(a.manhattan(&b), 8);
+    }
+
+    #[test]
+    fn lines_include_both_ends_and_have_no_gaps() {
+        let a = TileCoord::new(0, 0);
+        let b = TileCoord::new(5, -2);
+
+        let line = a.line_to(&b);
+
+        assert_eq!(line.first(), Some(&a));
+        assert_eq!(line.last(), Some(&b));
+        assert_eq!(line.len(), 6);
+
+        for pair in line.windows(2) {
+            assert_eq!(pair[0].distance(&pair[1]), 1);
+        }
+    }
+}
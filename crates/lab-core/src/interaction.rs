@@ -4,8 +4,10 @@
 use crate::prelude::*;
 
 use bevy::ecs::ResMut;
+use lab_data::ItemDefinition;
+use serde::{Deserialize, Serialize};
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InteractableType {
     Player,
     Npc,
@@ -39,20 +41,24 @@ where
 {
     pub source: Entity,
     pub destination: Entity,
+    /// Whether the source bumped into the destination or used it
+    pub interaction_type: InteractionType,
     // resources
     pub items: &'a ResMut<'a, Items>,
-    pub world_catalog: InteractionCatalog<I, T, R>,
+    pub settings: &'a WorldSettings,
+    /// Borrowed for the duration of the interaction, it is never copied
+    pub world_catalog: &'a InteractionCatalog<I, T, R>,
     pub interaction_query: &'a Query<
         'a,
         (
             Entity,
             &'a InteractableType,
             &'a ObjectState,
-            &'a I,
+            &'a InteractionKey,
             &'a Inventory,
         ),
     >,
-    pub item_query: &'a Query<'a, (Entity, &'a ItemType, &'a Named)>,
+    pub item_query: &'a Query<'a, (Entity, &'a ItemType, &'a Named, &'a ItemDefinition)>,
 }
 
 #[derive(Debug, Bundle, Default)]
@@ -72,6 +78,7 @@ impl Interactable {
 }
 
 /// Events
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum InteractionType {
     Collision,
     Action(String),
@@ -81,3 +88,10 @@ pub struct InteractionEvent {
     pub destination: Entity,
     pub interaction_type: InteractionType,
 }
+
+/// Sent when an entity is removed from the world for good, so systems
+/// holding on to it can let go.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityDespawnedEvent {
+    pub entity: Entity,
+}
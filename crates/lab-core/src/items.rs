@@ -0,0 +1,33 @@
+/// Module for the items of the game
+///
+/// Items are content, every json, ron or toml file in ITEMS_PATH holds
+/// ItemDefinitions and is loaded into the ItemCatalog at startup. Code refers
+/// to items by the id of their definition, e.g. to put one in an inventory.
+use crate::{ItemComponents, Named, Weight};
+use bevy::prelude::*;
+use lab_data::{Catalog, ItemDefinition};
+
+/// Folder the item definitions are read from
+pub const ITEMS_PATH: &str = "resources/items";
+
+/// Resource holding every item definition by id
+pub type ItemCatalog = Catalog<ItemDefinition>;
+
+impl From<&ItemDefinition> for ItemComponents {
+    fn from(definition: &ItemDefinition) -> Self {
+        ItemComponents {
+            name: Named(definition.name.clone()),
+            weight: Weight(definition.weight),
+            item_type: definition.item_type.clone(),
+            item_slot: definition.item_slot.clone(),
+            description: definition.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn load_item_catalog_system(mut catalog: ResMut<ItemCatalog>) {
+    catalog.load(ITEMS_PATH);
+
+    log::info!("Loaded {} item definitions", catalog.len());
+}
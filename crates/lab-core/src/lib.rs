@@ -1,15 +1,28 @@
 pub use bevy::{input::mouse::MouseButtonInput, prelude::*, render::camera::*};
 
+pub use crate::collider::*;
+pub use crate::coords::*;
 pub use crate::interaction::*;
+pub use crate::items::*;
+pub use crate::spatial::*;
+pub use crate::state::*;
 pub use crate::tiles::*;
+pub use crate::turns::*;
 pub use crate::world::*;
+pub use lab_data::{ItemSlot, ItemType};
 
 use rand::distributions::{Distribution, Standard};
 use rand::Rng;
 
+mod collider;
+mod coords;
 mod interaction;
+mod items;
+mod spatial;
+mod state;
 mod systems;
 mod tiles;
+mod turns;
 mod world;
 
 pub mod prelude {
@@ -34,15 +47,22 @@ pub struct CorePlugin;
 impl Plugin for CorePlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_resource::<Items>()
+            .init_resource::<HandleAllocator>()
+            .init_resource::<SpatialIndex>()
             .init_resource::<WorldSettings>()
+            .init_resource::<Turns>()
+            .add_event::<TurnEvent>()
+            .add_event::<StateChangeEvent>()
             .init_resource::<AdventureLog>()
-            .init_resource::<InputTimer>();
+            .init_resource::<InputTimer>()
+            .init_resource::<ItemCatalog>()
+            .add_startup_system_to_stage(stages::INIT, load_item_catalog_system.system());
     }
 }
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Properties, Serialize, Debug, Clone, PartialEq)]
+#[derive(Properties, Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Named(pub String);
 
 impl Default for Named {
@@ -59,6 +79,7 @@ impl Default for InputTimer {
     }
 }
 pub struct Despawn;
+/// Objects that slide away when they are walked into
 #[derive(Debug, Clone, Copy)]
 pub struct Moveable;
 /// This defines an entity as zoomable. It will be modified by the zoom system.
@@ -77,11 +98,25 @@ pub struct MenuDefinition {
     pub items: Vec<MenuItem>,
 }
 
+/// How players and NPCs get around the world
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MovementMode {
+    /// Anywhere, a little every frame
+    Free,
+    /// A whole tile at a time, see GridStep
+    Grid,
+}
+
 pub struct WorldSettings {
     pub tile_size: f32,
     pub base_player_speed: f32,
     pub base_npc_speed: f32,
     pub base_scale: f32,
+    pub movement_mode: MovementMode,
+    /// Seconds a step from one tile to the next takes in grid mode
+    pub step_seconds: f32,
+    /// NPCs only act when the player does, see TurnScheduler
+    pub turn_mode: bool,
 }
 
 impl Default for WorldSettings {
@@ -91,6 +126,21 @@ impl Default for WorldSettings {
             base_player_speed: 8.,
             base_npc_speed: 8.,
             base_scale: 5.,
+            movement_mode: MovementMode::Free,
+            step_seconds: 0.2,
+            turn_mode: false,
+        }
+    }
+}
+
+impl WorldSettings {
+    /// How players and NPCs move right now. Turns are counted in steps, so in
+    /// turn mode everything moves on the grid whatever the movement mode says.
+    pub fn movement(&self) -> MovementMode {
+        if self.turn_mode {
+            MovementMode::Grid
+        } else {
+            self.movement_mode
         }
     }
 }
@@ -195,6 +245,45 @@ impl Movement {
     }
 }
 
+/// A step to the next tile in grid mode, what is on that tile gets a
+/// collision before the step is taken and a Block cancels it.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct GridStep {
+    pub from: Vec3,
+    pub to: Vec3,
+    pub seconds: f32,
+    pub elapsed: f32,
+    /// The tile it goes to was checked for collisions
+    pub checked: bool,
+}
+
+impl GridStep {
+    pub fn new(from: Vec3, to: Vec3, seconds: f32) -> GridStep {
+        GridStep {
+            from,
+            to,
+            seconds,
+            elapsed: 0.,
+            checked: false,
+        }
+    }
+
+    /// Where along the step the mover is
+    pub fn position(&self) -> Vec3 {
+        let progress = if self.seconds > 0. {
+            (self.elapsed / self.seconds).min(1.)
+        } else {
+            1.
+        };
+
+        self.from + (self.to - self.from) * progress
+    }
+
+    pub fn finished(&self) -> bool {
+        self.elapsed >= self.seconds
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum CardinalDirection {
     None,
@@ -204,6 +293,46 @@ pub enum CardinalDirection {
     West,
 }
 
+impl CardinalDirection {
+    /// The direction along the longer axis of the vector
+    pub fn from_vec2(v: Vec2) -> CardinalDirection {
+        if v.x() == 0. && v.y() == 0. {
+            CardinalDirection::None
+        } else if v.x().abs() >= v.y().abs() {
+            if v.x() > 0. {
+                CardinalDirection::East
+            } else {
+                CardinalDirection::West
+            }
+        } else if v.y() > 0. {
+            CardinalDirection::North
+        } else {
+            CardinalDirection::South
+        }
+    }
+
+    /// Unit vector pointing in the direction
+    pub fn vec2(&self) -> Vec2 {
+        match self {
+            CardinalDirection::North => Vec2::new(0., 1.),
+            CardinalDirection::South => Vec2::new(0., -1.),
+            CardinalDirection::East => Vec2::new(1., 0.),
+            CardinalDirection::West => Vec2::new(-1., 0.),
+            CardinalDirection::None => Vec2::zero(),
+        }
+    }
+}
+
+/// The direction an entity last moved in, it is what the entity is looking at
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Facing(pub CardinalDirection);
+
+impl Default for Facing {
+    fn default() -> Self {
+        Facing(CardinalDirection::South)
+    }
+}
+
 impl Distribution<CardinalDirection> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CardinalDirection {
         match rng.gen_range(0, 5) {
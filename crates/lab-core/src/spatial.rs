@@ -0,0 +1,185 @@
+/// Module for finding entities by where they are in the world
+///
+/// The world is split into square cells, every entry is stored in each cell
+/// its bounds touch. Lookups only have to check the entries in the cells the
+/// queried area touches instead of every entity in the world.
+use bevy::prelude::*;
+use std::{collections::HashMap, hash::Hash};
+
+/// Axis aligned bounding box, in world coordinates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl Bounds {
+    pub fn from_center(center: Vec2, size: Vec2) -> Bounds {
+        Bounds {
+            min: center - size / 2.,
+            max: center + size / 2.,
+        }
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) / 2.
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        point.x() >= self.min.x()
+            && point.x() <= self.max.x()
+            && point.y() >= self.min.y()
+            && point.y() <= self.max.y()
+    }
+
+    pub fn overlaps(&self, other: &Bounds) -> bool {
+        self.min.x() <= other.max.x()
+            && self.max.x() >= other.min.x()
+            && self.min.y() <= other.max.y()
+            && self.max.y() >= other.min.y()
+    }
+
+    /// Like overlaps, but boxes that only touch don't count
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.min.x() < other.max.x()
+            && self.max.x() > other.min.x()
+            && self.min.y() < other.max.y()
+            && self.max.y() > other.min.y()
+    }
+}
+
+type Cell = (i32, i32);
+
+/// Uniform grid of keys, see SpatialIndex for the entity version
+#[derive(Debug)]
+pub struct SpatialGrid<K: Copy + Eq + Hash> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<K>>,
+    bounds: HashMap<K, Bounds>,
+}
+
+/// Resource indexing the bounds of entities in the world
+pub type SpatialIndex = SpatialGrid<Entity>;
+
+impl<K: Copy + Eq + Hash> Default for SpatialGrid<K> {
+    fn default() -> Self {
+        // a few tiles per cell, most sprites are 16 pixels
+        SpatialGrid::new(64.)
+    }
+}
+
+impl<K: Copy + Eq + Hash> SpatialGrid<K> {
+    pub fn new(cell_size: f32) -> SpatialGrid<K> {
+        SpatialGrid {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+
+    pub fn get(&self, key: K) -> Option<&Bounds> {
+        self.bounds.get(&key)
+    }
+
+    /// Add a key or move it to new bounds
+    pub fn insert(&mut self, key: K, bounds: Bounds) {
+        if let Some(old) = self.bounds.get(&key) {
+            if *old == bounds {
+                return;
+            }
+
+            self.remove(key);
+        }
+
+        for cell in self.cells_in(&bounds) {
+            self.cells.entry(cell).or_insert_with(Vec::new).push(key);
+        }
+
+        self.bounds.insert(key, bounds);
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<Bounds> {
+        let bounds = self.bounds.remove(&key)?;
+
+        for cell in self.cells_in(&bounds) {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|k| *k != key);
+
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+
+        Some(bounds)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+    }
+
+    /// Every key with bounds overlapping `area`
+    pub fn query_aabb(&self, area: &Bounds) -> Vec<K> {
+        let mut found = Vec::new();
+
+        for cell in self.cells_in(area) {
+            if let Some(keys) = self.cells.get(&cell) {
+                for key in keys {
+                    // large entries live in several cells
+                    if found.contains(key) {
+                        continue;
+                    }
+
+                    if self.bounds[key].overlaps(area) {
+                        found.push(*key);
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Every key with bounds containing `point`
+    pub fn query_point(&self, point: Vec2) -> Vec<K> {
+        match self.cells.get(&self.cell(point)) {
+            Some(keys) => keys
+                .iter()
+                .filter(|key| self.bounds[*key].contains(point))
+                .cloned()
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    fn cell(&self, point: Vec2) -> Cell {
+        (
+            (point.x() / self.cell_size).floor() as i32,
+            (point.y() / self.cell_size).floor() as i32,
+        )
+    }
+
+    fn cells_in(&self, bounds: &Bounds) -> impl Iterator<Item = Cell> {
+        let (min_x, min_y) = self.cell(bounds.min);
+        let (max_x, max_y) = self.cell(bounds.max);
+
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+}
@@ -0,0 +1,412 @@
+/// Module for the state of objects in the world
+///
+/// An ObjectState maps keys to typed values, e.g. whether a door is open or
+/// how many hit points a wall has left. Getters report a missing key or a
+/// value of another type instead of guessing. Every key that is set to a new
+/// value or removed is remembered until the changes are taken, lab-world
+/// sends them out as StateChangeEvents.
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Debug, Display};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StateValue {
+    Bool(bool),
+    Int(i32),
+    Float(f32),
+    Str(String),
+    List(Vec<StateValue>),
+}
+
+impl StateValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            StateValue::Bool(_) => "bool",
+            StateValue::Int(_) => "int",
+            StateValue::Float(_) => "float",
+            StateValue::Str(_) => "string",
+            StateValue::List(_) => "list",
+        }
+    }
+}
+
+impl From<bool> for StateValue {
+    fn from(value: bool) -> Self {
+        StateValue::Bool(value)
+    }
+}
+
+impl From<i32> for StateValue {
+    fn from(value: i32) -> Self {
+        StateValue::Int(value)
+    }
+}
+
+impl From<f32> for StateValue {
+    fn from(value: f32) -> Self {
+        StateValue::Float(value)
+    }
+}
+
+impl From<String> for StateValue {
+    fn from(value: String) -> Self {
+        StateValue::Str(value)
+    }
+}
+
+impl From<&str> for StateValue {
+    fn from(value: &str) -> Self {
+        StateValue::Str(value.to_string())
+    }
+}
+
+impl From<Vec<StateValue>> for StateValue {
+    fn from(value: Vec<StateValue>) -> Self {
+        StateValue::List(value)
+    }
+}
+
+#[derive(Clone, PartialEq)]
+pub enum StateError {
+    Missing(String),
+    /// The key holds a value of another type
+    Mismatch {
+        key: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// Both states hold a different value for the key
+    Conflict(String),
+}
+
+impl Debug for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StateError::Missing(key) => write!(f, "State Error: no value for {}", key),
+            StateError::Mismatch {
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "State Error: expected {} to be a {}, found a {}",
+                key, expected, found
+            ),
+            StateError::Conflict(key) => {
+                write!(f, "State Error: conflicting values for {}", key)
+            }
+        }
+    }
+}
+
+impl Display for StateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+/// What a merge does with keys both states hold
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MergePolicy {
+    /// Keep the value we have, only take keys we don't have
+    Ours,
+    /// Take the value of the other state
+    Theirs,
+    /// Like Theirs, but lists are appended to instead of replaced
+    Append,
+    /// Fail on the first key holding different values, nothing is merged
+    Strict,
+}
+
+/// Sent for every key of an ObjectState that changed
+#[derive(Clone, Debug, PartialEq)]
+pub struct StateChangeEvent {
+    pub entity: Entity,
+    pub key: String,
+    /// The new value, nothing when the key was removed
+    pub value: Option<StateValue>,
+}
+
+/// Component for tracking tile state
+/// e.g. let is_open = tile_state.get_bool("open")?;
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ObjectState {
+    values: HashMap<String, StateValue>,
+    /// Keys set or removed since the changes were last taken
+    #[serde(skip)]
+    changed: HashSet<String>,
+}
+
+/// States are equal when they hold the same values, changes don't count
+impl PartialEq for ObjectState {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
+}
+
+impl ObjectState {
+    /// Set the key, it only counts as a change when the value is different
+    pub fn set(&mut self, key: &str, value: impl Into<StateValue>) {
+        let value = value.into();
+
+        if self.values.get(key) != Some(&value) {
+            self.values.insert(key.to_string(), value);
+            self.changed.insert(key.to_string());
+        }
+    }
+
+    pub fn set_bool(&mut self, key: &str, value: bool) {
+        self.set(key, value);
+    }
+
+    pub fn set_int(&mut self, key: &str, value: i32) {
+        self.set(key, value);
+    }
+
+    pub fn set_float(&mut self, key: &str, value: f32) {
+        self.set(key, value);
+    }
+
+    pub fn set_string(&mut self, key: &str, value: String) {
+        self.set(key, value);
+    }
+
+    pub fn remove(&mut self, key: &str) -> Option<StateValue> {
+        let removed = self.values.remove(key);
+
+        if removed.is_some() {
+            self.changed.insert(key.to_string());
+        }
+
+        removed
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &StateValue)> {
+        self.values.iter()
+    }
+
+    pub fn get(&self, key: &str) -> Result<&StateValue, StateError> {
+        self.values
+            .get(key)
+            .ok_or_else(|| StateError::Missing(key.to_string()))
+    }
+
+    fn mismatch(key: &str, expected: &'static str, found: &StateValue) -> StateError {
+        StateError::Mismatch {
+            key: key.to_string(),
+            expected,
+            found: found.type_name(),
+        }
+    }
+
+    pub fn get_bool(&self, key: &str) -> Result<bool, StateError> {
+        match self.get(key)? {
+            StateValue::Bool(value) => Ok(*value),
+            other => Err(ObjectState::mismatch(key, "bool", other)),
+        }
+    }
+
+    pub fn get_int(&self, key: &str) -> Result<i32, StateError> {
+        match self.get(key)? {
+            StateValue::Int(value) => Ok(*value),
+            other => Err(ObjectState::mismatch(key, "int", other)),
+        }
+    }
+
+    pub fn get_float(&self, key: &str) -> Result<f32, StateError> {
+        match self.get(key)? {
+            StateValue::Float(value) => Ok(*value),
+            other => Err(ObjectState::mismatch(key, "float", other)),
+        }
+    }
+
+    pub fn get_string(&self, key: &str) -> Result<&str, StateError> {
+        match self.get(key)? {
+            StateValue::Str(value) => Ok(value),
+            other => Err(ObjectState::mismatch(key, "string", other)),
+        }
+    }
+
+    pub fn get_list(&self, key: &str) -> Result<&[StateValue], StateError> {
+        match self.get(key)? {
+            StateValue::List(value) => Ok(value),
+            other => Err(ObjectState::mismatch(key, "list", other)),
+        }
+    }
+
+    /// Bring the values of the other state in, keys both hold are settled by
+    /// the policy. Keys only we hold are kept.
+    pub fn merge(&mut self, other: &ObjectState, policy: MergePolicy) -> Result<(), StateError> {
+        if policy == MergePolicy::Strict {
+            if let Some((key, _)) = other
+                .values
+                .iter()
+                .find(|(key, value)| matches!(self.values.get(*key), Some(ours) if ours != *value))
+            {
+                return Err(StateError::Conflict(key.clone()));
+            }
+        }
+
+        for (key, theirs) in other.values.iter() {
+            let merged = match (self.values.get(key), theirs, policy) {
+                (None, _, _) => theirs.clone(),
+                (Some(_), _, MergePolicy::Ours) => continue,
+                (Some(StateValue::List(ours)), StateValue::List(more), MergePolicy::Append) => {
+                    StateValue::List(ours.iter().chain(more.iter()).cloned().collect())
+                }
+                (Some(_), _, _) => theirs.clone(),
+            };
+
+            self.set(key, merged);
+        }
+
+        Ok(())
+    }
+
+    /// Take over the values of the other state, removing keys it doesn't have
+    pub fn replace(&mut self, other: ObjectState) {
+        let removed: Vec<String> = self
+            .values
+            .keys()
+            .filter(|key| !other.values.contains_key(*key))
+            .cloned()
+            .collect();
+
+        for key in removed {
+            self.remove(&key);
+        }
+
+        for (key, value) in other.values {
+            self.set(&key, value);
+        }
+    }
+
+    pub fn has_changes(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    pub fn changed(&self, key: &str) -> bool {
+        self.changed.contains(key)
+    }
+
+    /// The keys that changed since the last time, sorted
+    pub fn take_changes(&mut self) -> Vec<String> {
+        let mut changes: Vec<String> = self.changed.drain().collect();
+
+        changes.sort();
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn door() -> ObjectState {
+        let mut state = ObjectState::default();
+
+        state.set_bool("open", false);
+        state.set_int("hit_points", 10);
+        state.set("keys", vec![StateValue::from("iron")]);
+        state.take_changes();
+
+        state
+    }
+
+    #[test]
+    fn getters_report_mismatches() {
+        let state = door();
+
+        assert_eq!(state.get_bool("open"), Ok(false));
+        assert_eq!(state.get_int("hit_points"), Ok(10));
+        assert_eq!(
+            state.get_int("open"),
+            Err(StateError::Mismatch {
+                key: "open".into(),
+                expected: "int",
+                found: "bool",
+            })
+        );
+        assert_eq!(
+            state.get_string("name"),
+            Err(StateError::Missing("name".into()))
+        );
+    }
+
+    #[test]
+    fn only_new_values_are_changes() {
+        let mut state = door();
+
+        state.set_bool("open", false);
+        assert!(!state.has_changes());
+
+        state.set_bool("open", true);
+        state.remove("hit_points");
+        state.remove("missing");
+
+        assert_eq!(state.take_changes(), vec!["hit_points", "open"]);
+        assert!(!state.has_changes());
+    }
+
+    #[test]
+    fn merges_follow_the_policy() {
+        let mut theirs = ObjectState::default();
+
+        theirs.set_bool("open", true);
+        theirs.set("keys", vec![StateValue::from("brass")]);
+        theirs.set_float("weight", 2.5);
+
+        let mut ours = door();
+        ours.merge(&theirs, MergePolicy::Ours).unwrap();
+
+        assert_eq!(ours.get_bool("open"), Ok(false));
+        assert_eq!(ours.get_float("weight"), Ok(2.5));
+        assert_eq!(ours.take_changes(), vec!["weight"]);
+
+        let mut appended = door();
+        appended.merge(&theirs, MergePolicy::Append).unwrap();
+
+        assert_eq!(appended.get_bool("open"), Ok(true));
+        assert_eq!(
+            appended.get_list("keys"),
+            Ok(&[StateValue::from("iron"), StateValue::from("brass")][..])
+        );
+        assert_eq!(appended.get_int("hit_points"), Ok(10));
+    }
+
+    #[test]
+    fn strict_merges_change_nothing_on_a_conflict() {
+        let mut theirs = ObjectState::default();
+
+        theirs.set_float("weight", 2.5);
+        theirs.set_bool("open", true);
+
+        let mut ours = door();
+
+        assert_eq!(
+            ours.merge(&theirs, MergePolicy::Strict),
+            Err(StateError::Conflict("open".into()))
+        );
+        assert_eq!(ours, door());
+        assert!(!ours.has_changes());
+    }
+
+    #[test]
+    fn replacing_removes_missing_keys() {
+        let mut state = door();
+        let mut other = door();
+
+        other.remove("keys");
+        other.set_bool("open", true);
+        state.replace(other);
+
+        assert!(!state.contains("keys"));
+        assert_eq!(state.take_changes(), vec!["keys", "open"]);
+    }
+}
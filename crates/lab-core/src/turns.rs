@@ -0,0 +1,214 @@
+/// Module for taking turns
+///
+/// Every actor gains energy by its Speed on every tick and gets a turn once
+/// it has TURN_ENERGY, taking the turn spends it. The world only moves on
+/// when the player acts, it ticks until the player can go again and everyone
+/// else takes the turns they earned along the way. The scheduler is generic
+/// over the key so it can be driven without a running game.
+use bevy::prelude::*;
+use std::{collections::HashMap, hash::Hash};
+
+/// Energy an actor needs to take a turn
+pub const TURN_ENERGY: u32 = 100;
+
+/// Energy an actor gains every tick, the default is a turn every ten ticks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Speed(pub u32);
+
+impl Default for Speed {
+    fn default() -> Self {
+        Speed(10)
+    }
+}
+
+/// Sent for every turn an actor other than the player gets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TurnEvent {
+    pub entity: Entity,
+}
+
+#[derive(Debug)]
+pub struct TurnScheduler<K: Copy + Eq + Hash> {
+    /// Actors in the order they joined, ties go to whoever joined first
+    actors: Vec<K>,
+    speeds: HashMap<K, Speed>,
+    energy: HashMap<K, u32>,
+}
+
+/// Resource scheduling the turns of the entities with a Speed
+pub type Turns = TurnScheduler<Entity>;
+
+impl<K: Copy + Eq + Hash> Default for TurnScheduler<K> {
+    fn default() -> Self {
+        TurnScheduler {
+            actors: Vec::new(),
+            speeds: HashMap::new(),
+            energy: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Copy + Eq + Hash> TurnScheduler<K> {
+    pub fn new() -> TurnScheduler<K> {
+        TurnScheduler::default()
+    }
+
+    /// Add the actor, actors that are already scheduled only get the new speed
+    pub fn add(&mut self, actor: K, speed: Speed) {
+        if self.speeds.insert(actor, speed).is_none() {
+            self.actors.push(actor);
+            self.energy.insert(actor, 0);
+        }
+    }
+
+    pub fn remove(&mut self, actor: K) {
+        self.actors.retain(|a| *a != actor);
+        self.speeds.remove(&actor);
+        self.energy.remove(&actor);
+    }
+
+    pub fn contains(&self, actor: K) -> bool {
+        self.speeds.contains_key(&actor)
+    }
+
+    pub fn len(&self) -> usize {
+        self.actors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actors.is_empty()
+    }
+
+    pub fn energy(&self, actor: K) -> u32 {
+        *self.energy.get(&actor).unwrap_or(&0)
+    }
+
+    pub fn is_ready(&self, actor: K) -> bool {
+        self.energy(actor) >= TURN_ENERGY
+    }
+
+    /// Everyone gains energy by their speed, even the slowest gain a little
+    pub fn tick(&mut self) {
+        for actor in self.actors.iter() {
+            let speed = self.speeds.get(actor).map(|s| s.0).unwrap_or(0).max(1);
+
+            *self.energy.entry(*actor).or_insert(0) += speed;
+        }
+    }
+
+    /// Spend a turn worth of energy, false when the actor didn't have it
+    pub fn spend(&mut self, actor: K) -> bool {
+        match self.energy.get_mut(&actor) {
+            Some(energy) if *energy >= TURN_ENERGY => {
+                *energy -= TURN_ENERGY;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The ready actor, other than the player, with the most energy
+    fn next(&self, player: K) -> Option<K> {
+        self.actors
+            .iter()
+            .filter(|actor| **actor != player && self.is_ready(**actor))
+            .fold(None, |best: Option<K>, actor| match best {
+                Some(best) if self.energy(best) >= self.energy(*actor) => Some(best),
+                _ => Some(*actor),
+            })
+    }
+
+    /// The player took a turn, runs the world until the player can go again and
+    /// returns the turns everyone else got in the order they take them.
+    pub fn player_acted(&mut self, player: K) -> Vec<K> {
+        let mut turns = Vec::new();
+
+        if !self.contains(player) {
+            return turns;
+        }
+
+        // a player acting before having the energy still pays for it
+        if !self.spend(player) {
+            self.energy.insert(player, 0);
+        }
+
+        loop {
+            while let Some(actor) = self.next(player) {
+                self.spend(actor);
+                turns.push(actor);
+            }
+
+            if self.is_ready(player) {
+                return turns;
+            }
+
+            self.tick();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAYER: u32 = 0;
+
+    fn scheduler(actors: &[(u32, u32)]) -> TurnScheduler<u32> {
+        let mut scheduler = TurnScheduler::new();
+
+        for (actor, speed) in actors {
+            scheduler.add(*actor, Speed(*speed));
+        }
+
+        scheduler
+    }
+
+    #[test]
+    fn equal_speeds_take_turns_in_step() {
+        let mut turns = scheduler(&[(PLAYER, 10), (1, 10), (2, 10)]);
+
+        assert_eq!(turns.player_acted(PLAYER), vec![1, 2]);
+        assert_eq!(turns.player_acted(PLAYER), vec![1, 2]);
+    }
+
+    #[test]
+    fn faster_actors_get_more_turns() {
+        let mut turns = scheduler(&[(PLAYER, 10), (1, 20), (2, 5)]);
+
+        let mut taken: Vec<u32> = Vec::new();
+
+        for _ in 0..4 {
+            taken.extend(turns.player_acted(PLAYER));
+        }
+
+        assert_eq!(taken.iter().filter(|a| **a == 1).count(), 8);
+        assert_eq!(taken.iter().filter(|a| **a == 2).count(), 2);
+    }
+
+    #[test]
+    fn the_player_can_act_again_once_the_world_has_moved() {
+        let mut turns = scheduler(&[(PLAYER, 5), (1, 10)]);
+
+        assert_eq!(turns.player_acted(PLAYER), vec![1, 1]);
+        assert!(turns.is_ready(PLAYER));
+        assert!(!turns.is_ready(1));
+    }
+
+    #[test]
+    fn removed_actors_get_no_turns() {
+        let mut turns = scheduler(&[(PLAYER, 10), (1, 10), (2, 10)]);
+
+        turns.remove(1);
+
+        assert_eq!(turns.player_acted(PLAYER), vec![2]);
+        assert!(!turns.contains(1));
+    }
+
+    #[test]
+    fn players_that_are_not_scheduled_do_not_advance_the_world() {
+        let mut turns = scheduler(&[(1, 10)]);
+
+        assert!(turns.player_acted(PLAYER).is_empty());
+        assert_eq!(turns.energy(1), 0);
+    }
+}
@@ -1,8 +1,7 @@
 use crate::interaction::*;
 use bevy::prelude::*;
-use defaults::*;
 use lab_data::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{
     collections::{
         hash_map::{Keys, Values},
@@ -16,15 +15,30 @@ use std::{
 };
 use uuid::Uuid;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WorldLocation {
     World,
     Inventory,
     Labyrinth,
     BarRoom,
 }
+
+/// How far down the labyrinth an entity is, 0 is the ground floor
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Level(pub i32);
+
+impl Level {
+    /// Where on the map an entity on this level is
+    pub fn world_location(&self) -> WorldLocation {
+        if self.0 > 0 {
+            WorldLocation::Labyrinth
+        } else {
+            WorldLocation::World
+        }
+    }
+}
 // Component to work with Translations to get "World Locations"
-#[derive(Clone, Debug, Copy, PartialEq, Properties)]
+#[derive(Clone, Debug, Copy, PartialEq, Properties, Serialize, Deserialize)]
 pub struct Location(
     pub f32,
     pub f32,
@@ -59,6 +73,28 @@ impl From<Location> for Vec2 {
         Vec2::new(x.0, x.1)
     }
 }
+/// Stable name of an interaction in the InteractionCatalog, e.g. "door.open".
+/// Entities carry the key so maps and saves can refer to behavior by name.
+#[derive(Default, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct InteractionKey(pub String);
+
+impl InteractionKey {
+    pub fn new(key: &str) -> InteractionKey {
+        InteractionKey(key.to_string())
+    }
+
+    /// The default key has no interaction registered
+    pub fn is_none(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<&str> for InteractionKey {
+    fn from(key: &str) -> Self {
+        InteractionKey::new(key)
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct InteractionCatalog<I, T: CatalogItem + Sync + Send + Clone, R: Sync + Send + Clone>
 where
@@ -69,6 +105,7 @@ where
     pub cur_id: u32,
     pub components: HashMap<String, T>,
     pub interactions: HashMap<WorldHandle<I>, Arc<I>>,
+    pub keys: HashMap<InteractionKey, WorldHandle<I>>,
 }
 
 impl<I, T: CatalogItem + Sync + Send + Clone, R: Sync + Send + Clone> InteractionCatalog<I, T, R>
@@ -84,16 +121,41 @@ where
         }
     }
     pub fn add_interaction(&mut self, interaction: I) -> WorldHandle<I> {
-        let handle = WorldHandle::<I> {
-            id: HandleId::new(),
-            ..Default::default()
-        };
+        // handles follow the order interactions are added in, so the same
+        // registrations give the same handles every run
+        let handle = WorldHandle::<I>::from_content(&("interaction", self.cur_id));
 
+        self.cur_id += 1;
         self.interactions.insert(handle, Arc::new(interaction));
 
         handle.clone()
     }
 
+    /// Register an interaction under a stable key, replacing any interaction
+    /// that was registered with the same key.
+    pub fn register_interaction(&mut self, key: &str, interaction: I) -> InteractionKey {
+        let key = InteractionKey::new(key);
+        let handle = WorldHandle::<I>::from_content(&("interaction", &key.0));
+
+        if self.keys.insert(key.clone(), handle).is_some() {
+            log::warn!("Replacing the interaction registered as {}", key.0);
+        }
+
+        self.interactions.insert(handle, Arc::new(interaction));
+
+        key
+    }
+
+    pub fn get_interaction_by_key(&self, key: &InteractionKey) -> Option<Arc<I>> {
+        self.keys
+            .get(key)
+            .and_then(|handle| self.get_interaction(*handle))
+    }
+
+    pub fn interaction_keys(&self) -> Keys<'_, InteractionKey, WorldHandle<I>> {
+        self.keys.keys()
+    }
+
     pub fn names(&self) -> Keys<'_, String, T> {
         self.components.keys()
     }
@@ -150,30 +212,6 @@ pub struct Visible;
 #[derive(Copy, Clone, Debug)]
 pub struct Solid;
 
-#[derive(Clone, Debug, PartialEq, Defaults)]
-#[def = "Misc"]
-pub enum ItemType {
-    Weapon,
-    Potion,
-    Brew,
-    Armor,
-    Ingredient,
-    Key,
-    Misc,
-    Undefined,
-}
-#[derive(Clone, Debug, PartialEq, Defaults)]
-#[def = "None"]
-pub enum ItemSlot {
-    LeftHand,
-    RightHand,
-    Head,
-    Body,
-    Legs,
-    Magic,
-    None,
-}
-
 #[derive(Clone, Copy, Debug, PartialEq, Default)]
 pub struct Tile;
 
@@ -194,9 +232,173 @@ pub struct ItemComponents {
 pub struct HandleId(pub Uuid);
 
 impl HandleId {
-    pub fn new() -> HandleId {
+    /// A random id, a new one every run. Prefer a HandleAllocator or
+    /// `from_content` so that the same world gets the same handles.
+    pub fn random() -> HandleId {
         HandleId(Uuid::new_v4())
     }
+
+    /// Id of a handle that was never assigned
+    pub fn nil() -> HandleId {
+        HandleId(Uuid::nil())
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.0.is_nil()
+    }
+
+    /// An id derived from content, the same content always gives the same id
+    pub fn from_content<H: Hash + ?Sized>(content: &H) -> HandleId {
+        let mut hasher = StableHasher::default();
+
+        content.hash(&mut hasher);
+
+        HandleId(Uuid::from_u128(hasher.finish_u128()))
+    }
+
+    /// The nth id of the sequence for a seed
+    pub fn from_seed(seed: u64, n: u64) -> HandleId {
+        HandleId::from_content(&(seed, n))
+    }
+}
+
+/// FNV-1a over two lanes. Unlike the std hasher its output is part of
+/// our save format, so it must never change. Integers are hashed as little
+/// endian bytes so every platform agrees on the ids.
+struct StableHasher {
+    high: u64,
+    low: u64,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher {
+            high: 0xcbf2_9ce4_8422_2325,
+            low: 0x6c62_272e_07bb_0142,
+        }
+    }
+}
+
+impl StableHasher {
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn finish_u128(&self) -> u128 {
+        ((self.high as u128) << 64) | self.low as u128
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.high = (self.high ^ *byte as u64).wrapping_mul(Self::PRIME);
+            self.low = (self.low ^ self.high).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.write(&[i]);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    // usize is widened so 32 and 64 bit builds agree
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i8(&mut self, i: i8) {
+        self.write_u8(i as u8);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.high ^ self.low
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HandleStrategy {
+    /// Handles follow a sequence, the same seed and the same order of
+    /// allocation gives the same handles.
+    Seeded(u64),
+    Random,
+}
+
+/// Resource that hands out new handles
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HandleAllocator {
+    pub strategy: HandleStrategy,
+    allocated: u64,
+}
+
+impl Default for HandleAllocator {
+    fn default() -> Self {
+        HandleAllocator::seeded(0)
+    }
+}
+
+impl HandleAllocator {
+    pub fn seeded(seed: u64) -> HandleAllocator {
+        HandleAllocator {
+            strategy: HandleStrategy::Seeded(seed),
+            allocated: 0,
+        }
+    }
+
+    pub fn random() -> HandleAllocator {
+        HandleAllocator {
+            strategy: HandleStrategy::Random,
+            allocated: 0,
+        }
+    }
+
+    pub fn next_id(&mut self) -> HandleId {
+        let id = match self.strategy {
+            HandleStrategy::Seeded(seed) => HandleId::from_seed(seed, self.allocated),
+            HandleStrategy::Random => HandleId::random(),
+        };
+
+        self.allocated += 1;
+
+        id
+    }
+
+    pub fn next<T>(&mut self) -> WorldHandle<T> {
+        WorldHandle::from_id(self.next_id())
+    }
 }
 
 #[derive(Properties)]
@@ -208,6 +410,28 @@ where
     #[property(ignore)]
     marker: PhantomData<T>,
 }
+impl<T> WorldHandle<T> {
+    pub fn from_id(id: HandleId) -> Self {
+        WorldHandle {
+            id,
+            marker: PhantomData,
+        }
+    }
+
+    /// A handle that is the same every time it is made from the same content
+    pub fn from_content<H: Hash + ?Sized>(content: &H) -> Self {
+        WorldHandle::from_id(HandleId::from_content(content))
+    }
+
+    pub fn random() -> Self {
+        WorldHandle::from_id(HandleId::random())
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.id.is_nil()
+    }
+}
+
 impl<T> Hash for WorldHandle<T> {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.id.hash(state);
@@ -229,12 +453,10 @@ impl<T> Debug for WorldHandle<T> {
     }
 }
 
+/// Handles start out unassigned, use a HandleAllocator to give them an identity
 impl<T> Default for WorldHandle<T> {
     fn default() -> Self {
-        WorldHandle {
-            id: HandleId::new(),
-            marker: PhantomData,
-        }
+        WorldHandle::from_id(HandleId::nil())
     }
 }
 
@@ -248,14 +470,27 @@ impl<T> Clone for WorldHandle<T> {
 }
 impl<T> Copy for WorldHandle<T> {}
 
+// Handles are persisted as their id, the marker only exists at compile time
+impl<T> Serialize for WorldHandle<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.id.serialize(serializer)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for WorldHandle<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HandleId::deserialize(deserializer).map(WorldHandle::from_id)
+    }
+}
+
 // SAFE: T is phantom data and Handle::id is an integer
 unsafe impl<T> Send for WorldHandle<T> {}
 unsafe impl<T> Sync for WorldHandle<T> {}
 
-#[derive(Copy, Clone, Debug, Properties, PartialEq, Default)]
+#[derive(Copy, Clone, Debug, Properties, PartialEq, Default, Serialize, Deserialize)]
 pub struct Weight(pub f32);
 
-#[derive(Clone, Default, Debug, PartialEq)]
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Inventory(pub Vec<WorldHandle<Item>>);
 
 #[derive(Clone, Default, Debug, PartialEq)]
@@ -264,3 +499,18 @@ pub struct ItemData {
     name: String,
     description: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_ids_never_change() {
+        // saves refer to items by these ids, a new id here breaks every save
+        assert_eq!(
+            HandleId::from_seed(1, 0),
+            HandleId(Uuid::parse_str("392209f1-4dea-4c24-2db0-d256f043e242").unwrap())
+        );
+        assert_ne!(HandleId::from_seed(1, 0), HandleId::from_seed(1, 1));
+    }
+}
@@ -0,0 +1,82 @@
+/// Module for the catalogs of content
+///
+/// A catalog holds every definition of one kind by its id, it is loaded from
+/// the content files of a folder at startup and code refers to the content by
+/// id, e.g. ItemCatalog and MobCatalog.
+use crate::*;
+use std::collections::HashMap;
+
+/// Every definition of a kind, by id
+#[derive(Clone, Debug)]
+pub struct Catalog<T: Definition> {
+    definitions: HashMap<String, T>,
+}
+
+impl<T: Definition> Default for Catalog<T> {
+    fn default() -> Self {
+        Catalog {
+            definitions: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Definition> Catalog<T> {
+    /// Add every content file in the folder, in the order of their names.
+    /// Entries that don't parse are left out, check-content says why
+    pub fn load(&mut self, path: &str) -> &mut Catalog<T> {
+        for file in content_files(path) {
+            match FileReader::<T>::open(&file) {
+                Ok(reader) => {
+                    for entry in reader {
+                        match entry {
+                            Ok(sourced) => {
+                                self.add(sourced.definition);
+                            }
+                            Err(err) => log::error!("{}, skipping the entry", err),
+                        }
+                    }
+                }
+                Err(err) => log::error!("{}, skipping the file", err),
+            }
+        }
+
+        self
+    }
+
+    /// Add the definition, replacing one with the same id
+    pub fn add(&mut self, definition: T) -> &mut Catalog<T> {
+        if definition.id().is_empty() {
+            log::warn!("Skipping {} {} without an id", T::KIND, definition.name());
+            return self;
+        }
+
+        if self.definitions.contains_key(definition.id()) {
+            log::warn!("Replacing the {} definition {}", T::KIND, definition.id());
+        }
+
+        let id = definition.id().to_string();
+        self.definitions.insert(id, definition);
+
+        self
+    }
+
+    pub fn get(&self, id: &str) -> Option<&T> {
+        self.definitions.get(id)
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.definitions.contains_key(id)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.definitions.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+}
@@ -1,8 +1,19 @@
 use defaults::*;
+use std::convert::TryFrom;
+use std::fmt::{Debug, Display};
 use std::fs;
+use std::str::FromStr;
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+mod catalog;
+mod reader;
+mod validate;
+
+pub use catalog::Catalog;
+pub use reader::*;
+pub use validate::{ContentError, ContentSet};
+
 #[derive(Defaults, PartialEq, Debug, Clone, Copy, Serialize, Deserialize)]
 #[def = "String"]
 pub enum AttributeType {
@@ -20,120 +31,235 @@ pub struct Attribute {
     value: String,
 }
 
-impl Into<String> for Attribute {
-    fn into(self) -> String {
-        self.value
+impl Attribute {
+    pub fn new(name: &str, attr_type: AttributeType, value: &str) -> Attribute {
+        Attribute {
+            attr_type,
+            name: name.to_string(),
+            value: value.to_string(),
+        }
     }
-}
-impl Into<u32> for Attribute {
-    fn into(self) -> u32 {
-        self.value.parse().unwrap()
+
+    pub fn name(&self) -> &str {
+        &self.name
     }
-}
-impl Into<bool> for Attribute {
-    fn into(self) -> bool {
-        self.value.parse().unwrap()
+
+    pub fn attr_type(&self) -> AttributeType {
+        self.attr_type
     }
-}
 
-impl Into<f32> for Attribute {
-    fn into(self) -> f32 {
-        self.value.parse().unwrap()
+    pub fn value(&self) -> &str {
+        &self.value
     }
 }
-#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
-pub struct ItemDefinition {
-    pub name: String,
-    pub description: String,
-    pub attributes: Vec<Attribute>,
+
+impl Into<String> for Attribute {
+    fn into(self) -> String {
+        self.value
+    }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub struct MobDefinition {
+/// The value of an attribute doesn't parse as its type
+#[derive(Clone, PartialEq)]
+pub struct AttributeError {
     pub name: String,
-    pub description: String,
-    pub attributes: Vec<Attribute>,
-    pub script: String,
+    pub expected: &'static str,
+    pub value: String,
 }
 
-trait ItemReader {
-    fn read_next(&mut self) -> ItemDefinition;
-    fn has_next(&self) -> bool;
-    fn reset(&mut self);
+impl Debug for AttributeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Attribute Error: {} should be a {}, not {:?}",
+            self.name, self.expected, self.value
+        )
+    }
 }
 
-//#[derive()]
-struct JsonFileReader<T> {
-    items: Vec<T>,
-    _filename: String,
-    current: i32,
-    count: usize,
-    // _pd : &'a PhantomData<T>
+impl Display for AttributeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
 }
 
-impl<T> Default for JsonFileReader<T> {
-    fn default() -> Self {
-        JsonFileReader {
-            //_pd: &PhantomData{},
-            items: Default::default(),
-            _filename: Default::default(),
-            current: -1 as i32,
-            count: 0,
+impl Attribute {
+    fn parse<T: FromStr>(&self, expected: &'static str) -> Result<T, AttributeError> {
+        self.value.parse().map_err(|_| AttributeError {
+            name: self.name.clone(),
+            expected,
+            value: self.value.clone(),
+        })
+    }
+
+    /// Check the value parses as the attribute type
+    pub fn check(&self) -> Result<(), AttributeError> {
+        match self.attr_type {
+            AttributeType::String => Ok(()),
+            AttributeType::UInt => self.parse::<u32>("UInt").map(|_| ()),
+            AttributeType::Int => self.parse::<i32>("Int").map(|_| ()),
+            AttributeType::Bool => self.parse::<bool>("Bool").map(|_| ()),
+            AttributeType::Float => self.parse::<f32>("Float").map(|_| ()),
         }
     }
 }
 
-impl<T> JsonFileReader<T>
-where
-    T: DeserializeOwned + Clone,
-{
-    #[allow(dead_code)]
-    fn new(filename: &str) -> JsonFileReader<T> {
-        let contents = fs::read_to_string(filename).expect("Could not read file");
-        let items: Vec<T> = serde_json::from_str(&contents[..]).unwrap();
-        let len = items.len();
-        JsonFileReader {
-            _filename: filename.into(),
-            items: items,
-            current: -1,
-            count: len,
-            ..Default::default()
-        }
+impl TryFrom<Attribute> for u32 {
+    type Error = AttributeError;
+
+    fn try_from(attribute: Attribute) -> Result<Self, Self::Error> {
+        attribute.parse("UInt")
     }
 }
 
-impl ItemReader for JsonFileReader<ItemDefinition> {
-    fn read_next(&mut self) -> ItemDefinition {
-        if self.has_next() {
-            self.current += 1;
+impl TryFrom<Attribute> for i32 {
+    type Error = AttributeError;
 
-            self.items[self.current as usize].clone()
-        } else {
-            panic!("Nothing left.");
-        }
+    fn try_from(attribute: Attribute) -> Result<Self, Self::Error> {
+        attribute.parse("Int")
     }
+}
+
+impl TryFrom<Attribute> for bool {
+    type Error = AttributeError;
 
-    fn has_next(&self) -> bool {
-        self.current < self.count as i32 - 1
+    fn try_from(attribute: Attribute) -> Result<Self, Self::Error> {
+        attribute.parse("Bool")
     }
+}
 
-    fn reset(&mut self) {
-        self.current = -1;
+impl TryFrom<Attribute> for f32 {
+    type Error = AttributeError;
+
+    fn try_from(attribute: Attribute) -> Result<Self, Self::Error> {
+        attribute.parse("Float")
     }
 }
 
-impl Iterator for JsonFileReader<ItemDefinition> {
-    type Item = ItemDefinition;
+#[derive(Clone, Debug, PartialEq, Defaults, Serialize, Deserialize)]
+#[def = "Misc"]
+pub enum ItemType {
+    Weapon,
+    Potion,
+    Brew,
+    Armor,
+    Ingredient,
+    Key,
+    Misc,
+    Undefined,
+}
+#[derive(Clone, Debug, PartialEq, Defaults, Serialize, Deserialize)]
+#[def = "None"]
+pub enum ItemSlot {
+    LeftHand,
+    RightHand,
+    Head,
+    Body,
+    Legs,
+    Magic,
+    None,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.has_next() {
-            Some(self.read_next())
-        } else {
-            None
-        }
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ItemDefinition {
+    /// Key of the item in the ItemCatalog
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub item_type: ItemType,
+    #[serde(default)]
+    pub item_slot: ItemSlot,
+    #[serde(default)]
+    pub weight: f32,
+    /// Name of the sprite drawn when the item lies in the world
+    #[serde(default)]
+    pub sprite: String,
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+}
+
+impl ItemDefinition {
+    pub fn attribute(&self, name: &str) -> Option<&Attribute> {
+        self.attributes.iter().find(|a| a.name == name)
     }
 }
 
+/// What a mob does when it gets to act
+#[derive(Clone, Copy, Debug, PartialEq, Defaults, Serialize, Deserialize)]
+#[def = "Wander"]
+pub enum Behavior {
+    /// Steps in a random direction
+    Wander,
+    /// Stays where it was put
+    Stand,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct MobDefinition {
+    /// Key of the mob in the MobCatalog
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Name of the sprite the mob is drawn with
+    #[serde(default)]
+    pub sprite: String,
+    /// Stats by name, e.g. a UInt attribute named strength
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+    /// Ids of the items in the ItemCatalog the mob carries
+    #[serde(default)]
+    pub inventory: Vec<String>,
+    /// Seconds between moves outside of turn mode, two when not given
+    #[serde(default)]
+    pub move_seconds: Option<f32>,
+    /// Energy gained every tick in turn mode, see Speed
+    #[serde(default)]
+    pub speed: Option<u32>,
+    #[serde(default)]
+    pub behavior: Behavior,
+    /// Key of the interaction run when something walks into the mob
+    #[serde(default)]
+    pub interaction: String,
+    #[serde(default)]
+    pub script: String,
+}
+
+/// Folder the recipe definitions are read from
+pub const RECIPES_PATH: &str = "resources/recipes";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RecipeIngredient {
+    /// Id of the item in the ItemCatalog
+    pub item: String,
+    #[serde(default = "RecipeIngredient::one")]
+    pub amount: u32,
+}
+
+impl RecipeIngredient {
+    fn one() -> u32 {
+        1
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct RecipeDefinition {
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub ingredients: Vec<RecipeIngredient>,
+    /// Id of the item the recipe makes
+    pub result: String,
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+}
+
 #[cfg(test)]
 mod tests {
     use crate::*;
@@ -148,10 +274,10 @@ mod tests {
 
         fs::write("debug.json", serde_json::to_string(&vec).unwrap()).unwrap();
 
-        let mut reader = JsonFileReader::new("debug.json");
+        let mut reader = FileReader::<ItemDefinition>::open("debug.json").unwrap();
 
         assert_eq!(true, reader.has_next());
-        assert_eq!(reader.read_next().name, "Test");
+        assert_eq!(reader.read_next().unwrap().unwrap().definition.name, "Test");
 
         // test iteration
 
@@ -166,6 +292,21 @@ mod tests {
 
         fs::remove_file("debug.json").unwrap();
     }
+    #[test]
+    fn item_definitions_only_need_a_name() {
+        let data = r#"[
+            { "name": "Pint Glass" },
+            { "id": "stout", "name": "Stout", "item_type": "Brew" }
+        ]"#;
+
+        let items: Vec<ItemDefinition> = serde_json::from_str(data).unwrap();
+
+        assert_eq!(items[0].id, "");
+        assert_eq!(items[0].item_type, ItemType::Misc);
+        assert_eq!(items[0].item_slot, ItemSlot::None);
+        assert_eq!(items[1].item_type, ItemType::Brew);
+    }
+
     #[test]
     fn item_attribtues() {
         let mut item = ItemDefinition {
@@ -187,7 +328,166 @@ mod tests {
         let item_vec: Vec<ItemDefinition> = serde_json::from_str(&data).unwrap();
 
         assert_eq!(item_vec[0].attributes[0].name, "one");
-        let coerced_int: u32 = item_vec[0].attributes[0].clone().into();
-        assert_eq!(coerced_int, 1);
+        let coerced_int = u32::try_from(item_vec[0].attributes[0].clone());
+        assert_eq!(coerced_int, Ok(1));
+    }
+
+    #[test]
+    fn attributes_check_their_type() {
+        let weight = Attribute::new("weight", AttributeType::Float, "heavy");
+
+        assert_eq!(
+            f32::try_from(weight.clone()),
+            Err(AttributeError {
+                name: "weight".into(),
+                expected: "Float",
+                value: "heavy".into(),
+            })
+        );
+        assert!(weight.check().is_err());
+        assert!(Attribute::new("name", AttributeType::String, "heavy")
+            .check()
+            .is_ok());
+    }
+
+    #[test]
+    fn content_errors_name_the_entry_and_field() {
+        let sourced = |index, definition| Sourced {
+            file: "items.json".to_string(),
+            entry: Entry::Index(index),
+            definition,
+        };
+
+        let content = ContentSet {
+            items: vec![
+                sourced(
+                    0,
+                    ItemDefinition {
+                        id: "stout".into(),
+                        name: "Stout".into(),
+                        sprite: "item_1".into(),
+                        ..Default::default()
+                    },
+                ),
+                sourced(
+                    1,
+                    ItemDefinition {
+                        id: "porter".into(),
+                        name: "Stout".into(),
+                        sprite: "item_404".into(),
+                        attributes: vec![Attribute::new("abv", AttributeType::UInt, "4.5")],
+                        ..Default::default()
+                    },
+                ),
+            ],
+            recipes: vec![Sourced {
+                file: "recipes.json".to_string(),
+                entry: Entry::Key("stout".into()),
+                definition: RecipeDefinition {
+                    id: "stout".into(),
+                    name: "Stout".into(),
+                    ingredients: vec![RecipeIngredient {
+                        item: "barley".into(),
+                        amount: 1,
+                    }],
+                    result: "stout".into(),
+                    ..Default::default()
+                },
+            }],
+            ..Default::default()
+        };
+
+        let sprites = vec!["item_1".to_string()].into_iter().collect();
+        let errors: Vec<String> = content
+            .validate(&sprites)
+            .into_iter()
+            .map(|err| format!("{} {} {}", err.file, err.entry.unwrap(), err.field.unwrap()))
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                "items.json 1 name",
+                "items.json 1 attributes[0]",
+                "items.json 1 sprite",
+                "recipes.json stout ingredients[0]",
+            ]
+        );
+    }
+
+    #[test]
+    fn every_format_reads_lists_and_maps() {
+        let files = [
+            (
+                "brews.json",
+                r#"{ "stout": { "name": "Stout", "item_type": "Brew" } }"#,
+            ),
+            (
+                "brews.ron",
+                r#"// ron lists
+                [ (id: "stout", name: "Stout", item_type: Brew) ]"#,
+            ),
+            (
+                "brews.ron",
+                r#"{
+                    // ron maps, with commas and brackets in strings
+                    "stout": (name: "Stout, }dark", item_type: Brew),
+                }"#,
+            ),
+            (
+                "brews.toml",
+                "[stout]\nname = \"Stout\"\nitem_type = \"Brew\"\n",
+            ),
+            (
+                "brews.toml",
+                "[[item]]\nid = \"stout\"\nname = \"Stout\"\nitem_type = \"Brew\"\n",
+            ),
+        ];
+
+        for (filename, contents) in files.iter() {
+            let items: Vec<Sourced<ItemDefinition>> = FileReader::parse(filename, contents)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+
+            assert_eq!(items.len(), 1, "{}", filename);
+            assert_eq!(items[0].definition.id, "stout", "{}", filename);
+            assert_eq!(
+                items[0].definition.item_type,
+                ItemType::Brew,
+                "{}",
+                filename
+            );
+        }
+    }
+
+    #[test]
+    fn bad_entries_do_not_hide_the_others() {
+        let data = r#"[{ "name": "Stout", "weight": "heavy" }, { "name": "Porter" }]"#;
+
+        let entries: Vec<Result<Sourced<ItemDefinition>, ContentError>> =
+            FileReader::parse("brews.json", data).unwrap().collect();
+
+        assert_eq!(
+            entries[0].as_ref().unwrap_err().entry,
+            Some(Entry::Index(0))
+        );
+        assert_eq!(entries[1].as_ref().unwrap().definition.name, "Porter");
+        assert!(FileReader::<ItemDefinition>::parse("brews.yaml", data).is_err());
+
+        let data = r#"[(name: "Stout", item_type: Lager), (name: "Porter", item_type: Brew),]"#;
+
+        let entries: Vec<Result<Sourced<ItemDefinition>, ContentError>> =
+            FileReader::parse("brews.ron", data).unwrap().collect();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].as_ref().unwrap_err().entry,
+            Some(Entry::Index(0))
+        );
+        assert_eq!(
+            entries[1].as_ref().unwrap().definition.item_type,
+            ItemType::Brew
+        );
     }
 }
@@ -0,0 +1,439 @@
+/// Module for reading content files
+///
+/// Definitions can be written in json, ron or toml, the extension of the file
+/// says which. A file holds either a list of definitions or a map of them by
+/// id. Toml files are maps, or a list when they only hold an array of tables
+/// such as [[item]]. The whole file is read and split into its entries when it
+/// is opened, but each entry is only parsed once it is read, so a bad entry
+/// doesn't hide the ones after it. Ron entries are split from the text since
+/// ron has no value that keeps enum names.
+use crate::*;
+use std::marker::PhantomData;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ContentFormat {
+    Json,
+    Ron,
+    Toml,
+}
+
+impl ContentFormat {
+    pub const EXTENSIONS: [&'static str; 3] = ["json", "ron", "toml"];
+
+    /// The format of the file by its extension
+    pub fn from_path(path: &str) -> Option<ContentFormat> {
+        match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Some(ContentFormat::Json),
+            Some("ron") => Some(ContentFormat::Ron),
+            Some("toml") => Some(ContentFormat::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Every content file in the folder, in the order of their names
+pub fn content_files(path: &str) -> Vec<String> {
+    let mut files: Vec<String> = match fs::read_dir(path) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path().to_string_lossy().to_string())
+            .filter(|file| ContentFormat::from_path(file).is_some())
+            .collect(),
+        Err(err) => {
+            log::warn!("Cannot read content from {}: {}", path, err);
+            Vec::new()
+        }
+    };
+
+    files.sort();
+    files
+}
+
+/// A definition that can be read from a content file
+pub trait Definition: DeserializeOwned + Clone {
+    /// What the definition describes, e.g. in log messages
+    const KIND: &'static str;
+
+    fn id(&self) -> &str;
+    fn name(&self) -> &str;
+    fn set_id(&mut self, id: &str);
+}
+
+impl Definition for ItemDefinition {
+    const KIND: &'static str = "item";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self.id = id.to_string();
+    }
+}
+
+impl Definition for MobDefinition {
+    const KIND: &'static str = "mob";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self.id = id.to_string();
+    }
+}
+
+impl Definition for RecipeDefinition {
+    const KIND: &'static str = "recipe";
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn set_id(&mut self, id: &str) {
+        self.id = id.to_string();
+    }
+}
+
+/// Where an entry is in its file
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Entry {
+    /// Position in a list
+    Index(usize),
+    /// Key in a map
+    Key(String),
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Entry::Index(index) => write!(f, "{}", index),
+            Entry::Key(key) => write!(f, "{}", key),
+        }
+    }
+}
+
+/// A definition along with where it was read from
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sourced<T> {
+    pub file: String,
+    pub entry: Entry,
+    pub definition: T,
+}
+
+/// Reads the definitions of a content file, one entry at a time
+pub trait ContentReader<T> {
+    /// The next definition, nothing once every entry has been read
+    fn read_next(&mut self) -> Option<Result<Sourced<T>, ContentError>>;
+    fn has_next(&self) -> bool;
+    fn reset(&mut self);
+}
+
+/// An entry of a file, parsed once it is read
+enum RawEntry {
+    Value(serde_json::Value),
+    /// The text of a ron entry
+    Ron(String),
+}
+
+/// Reads a content file in any of the ContentFormats
+pub struct FileReader<T> {
+    filename: String,
+    entries: Vec<(Entry, RawEntry)>,
+    current: usize,
+    definition: PhantomData<T>,
+}
+
+impl<T: Definition> FileReader<T> {
+    pub fn open(filename: &str) -> Result<FileReader<T>, ContentError> {
+        let contents = fs::read_to_string(filename)
+            .map_err(|err| ContentError::file(filename, err.to_string()))?;
+
+        FileReader::parse(filename, &contents)
+    }
+
+    /// Read the contents in the format the filename says
+    pub fn parse(filename: &str, contents: &str) -> Result<FileReader<T>, ContentError> {
+        let error = |message: String| ContentError::file(filename, message);
+
+        let entries = match ContentFormat::from_path(filename) {
+            Some(ContentFormat::Json) => {
+                value_entries(serde_json::from_str(contents).map_err(|e| error(e.to_string()))?)
+            }
+            Some(ContentFormat::Toml) => value_entries(table_list(
+                toml::from_str(contents).map_err(|e| error(e.to_string()))?,
+            )),
+            Some(ContentFormat::Ron) => Some(ron_entries(contents).map_err(error)?),
+            None => {
+                return Err(error(format!(
+                    "the extension should be one of {}",
+                    ContentFormat::EXTENSIONS.join(", ")
+                )))
+            }
+        }
+        .ok_or_else(|| error("expected a list or a map of definitions".into()))?;
+
+        Ok(FileReader {
+            filename: filename.to_string(),
+            entries,
+            current: 0,
+            definition: PhantomData,
+        })
+    }
+
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn value_entries(value: serde_json::Value) -> Option<Vec<(Entry, RawEntry)>> {
+    match value {
+        serde_json::Value::Array(list) => Some(
+            list.into_iter()
+                .enumerate()
+                .map(|(index, value)| (Entry::Index(index), RawEntry::Value(value)))
+                .collect(),
+        ),
+        serde_json::Value::Object(map) => Some(
+            map.into_iter()
+                .map(|(key, value)| (Entry::Key(key), RawEntry::Value(value)))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// The list of a toml file that only holds an array of tables, e.g. [[item]]
+fn table_list(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(mut map) if map.len() == 1 => {
+            let is_list = map.values().all(|value| match value {
+                serde_json::Value::Array(list) => list.iter().all(|entry| entry.is_object()),
+                _ => false,
+            });
+
+            if is_list {
+                map.values_mut().next().unwrap().take()
+            } else {
+                serde_json::Value::Object(map)
+            }
+        }
+        value => value,
+    }
+}
+
+/// Splits a ron list or map into the text of its entries. The attributes at
+/// the top of the file, e.g. #![enable(implicit_some)], go with every entry
+fn ron_entries(contents: &str) -> Result<Vec<(Entry, RawEntry)>, String> {
+    let mut start = skip_ron_trivia(contents, 0);
+    while contents[start..].starts_with("#!") {
+        let (_, close) = scan_ron(&contents[start + 3..], b',')?;
+        let close = close.ok_or("unclosed attribute")?;
+        start = skip_ron_trivia(contents, start + 3 + close + 1);
+    }
+    let header = &contents[..start];
+
+    let is_map = match contents[start..].chars().next() {
+        Some('[') => false,
+        Some('{') => true,
+        _ => return Err("expected a list or a map of definitions".into()),
+    };
+
+    let body = &contents[start + 1..];
+    let (separators, close) = scan_ron(body, b',')?;
+    let kind = if is_map { "map" } else { "list" };
+    let close = close.ok_or_else(|| format!("unclosed {}", kind))?;
+    if skip_ron_trivia(body, close + 1) < body.len() {
+        return Err(format!("unexpected text after the {}", kind));
+    }
+
+    let mut texts = Vec::new();
+    let mut from = 0;
+    for to in separators.into_iter().chain(std::iter::once(close)) {
+        texts.push(&body[from..to]);
+        from = to + 1;
+    }
+    // a trailing comma, or an empty list, leaves nothing after it
+    if let Some(last) = texts.last() {
+        if skip_ron_trivia(last, 0) == last.len() {
+            texts.pop();
+        }
+    }
+
+    let mut entries = Vec::new();
+    for (index, text) in texts.into_iter().enumerate() {
+        if !is_map {
+            entries.push((
+                Entry::Index(index),
+                RawEntry::Ron(format!("{}{}", header, text)),
+            ));
+            continue;
+        }
+
+        let (colons, _) = scan_ron(text, b':')?;
+        let colon = *colons.first().ok_or("expected a key and a definition")?;
+        let key: String = ron::from_str(&text[..colon]).map_err(|err| err.to_string())?;
+        let value = format!("{}{}", header, &text[colon + 1..]);
+        entries.push((Entry::Key(key), RawEntry::Ron(value)));
+    }
+
+    Ok(entries)
+}
+
+/// Position of the first thing after the whitespace and comments
+fn skip_ron_trivia(text: &str, mut pos: usize) -> usize {
+    loop {
+        pos += text[pos..].len() - text[pos..].trim_start().len();
+        let rest = &text[pos..];
+
+        if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+        } else if rest.starts_with("/*") {
+            pos += rest
+                .find("*/")
+                .map(|end| end + 2)
+                .unwrap_or_else(|| rest.len());
+        } else {
+            return pos;
+        }
+    }
+}
+
+/// Where the separator is in the ron text, leaving out strings, comments and
+/// anything in brackets, along with the bracket that closes the text if any
+fn scan_ron(text: &str, separator: u8) -> Result<(Vec<usize>, Option<usize>), String> {
+    let bytes = text.as_bytes();
+    let mut separators = Vec::new();
+    let mut depth = 0;
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        let rest = &text[pos..];
+
+        match bytes[pos] {
+            b'/' if rest.starts_with("//") => {
+                pos += rest.find('\n').unwrap_or(rest.len());
+            }
+            b'/' if rest.starts_with("/*") => {
+                pos += rest.find("*/").ok_or("unclosed comment")? + 1;
+            }
+            b'"' => pos += closing_quote(&rest[1..], "\"")? + 1,
+            b'\'' => pos += closing_quote(&rest[1..], "'")? + 1,
+            b'r' if raw_string_hashes(text, pos).is_some() => {
+                let hashes = raw_string_hashes(text, pos).unwrap();
+                let content = hashes + 2;
+                let end = format!("\"{}", "#".repeat(hashes));
+                pos += content + rest[content..].find(&end).ok_or("unclosed string")? + hashes;
+            }
+            b'(' | b'[' | b'{' => depth += 1,
+            b')' | b']' | b'}' if depth == 0 => return Ok((separators, Some(pos))),
+            b')' | b']' | b'}' => depth -= 1,
+            byte if byte == separator && depth == 0 => separators.push(pos),
+            _ => {}
+        }
+
+        pos += 1;
+    }
+
+    Ok((separators, None))
+}
+
+/// Position of the quote that closes a string or a char, past its escapes
+fn closing_quote(text: &str, quote: &str) -> Result<usize, String> {
+    let mut escaped = false;
+
+    for (pos, c) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if text[pos..].starts_with(quote) {
+            return Ok(pos);
+        }
+    }
+
+    Err("unclosed string".into())
+}
+
+/// The number of hashes of a raw string, e.g. r#"..."#, starting at the r
+fn raw_string_hashes(text: &str, pos: usize) -> Option<usize> {
+    let in_name = matches!(text[..pos].chars().last(), Some(c) if c.is_alphanumeric() || c == '_');
+    let hashes = text[pos + 1..].len() - text[pos + 1..].trim_start_matches('#').len();
+
+    if !in_name && text[pos + 1 + hashes..].starts_with('"') {
+        Some(hashes)
+    } else {
+        None
+    }
+}
+
+impl<T: Definition> ContentReader<T> for FileReader<T> {
+    fn read_next(&mut self) -> Option<Result<Sourced<T>, ContentError>> {
+        if !self.has_next() {
+            return None;
+        }
+
+        self.current += 1;
+        let (entry, raw) = &self.entries[self.current - 1];
+
+        let definition = match raw {
+            RawEntry::Value(value) => {
+                serde_json::from_value::<T>(value.clone()).map_err(|err| err.to_string())
+            }
+            RawEntry::Ron(text) => ron::from_str::<T>(text).map_err(|err| err.to_string()),
+        }
+        .map_err(|message| ContentError::entry(&self.filename, entry.clone(), message));
+
+        Some(definition.map(|mut definition| {
+            // entries of a map are known by their key
+            if let Entry::Key(key) = entry {
+                if definition.id().is_empty() {
+                    definition.set_id(key);
+                }
+            }
+
+            Sourced {
+                file: self.filename.clone(),
+                entry: entry.clone(),
+                definition,
+            }
+        }))
+    }
+
+    fn has_next(&self) -> bool {
+        self.current < self.entries.len()
+    }
+
+    fn reset(&mut self) {
+        self.current = 0;
+    }
+}
+
+impl<T: Definition> Iterator for FileReader<T> {
+    type Item = Result<Sourced<T>, ContentError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next()
+    }
+}
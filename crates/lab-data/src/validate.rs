@@ -0,0 +1,259 @@
+/// Module for checking content files
+///
+/// Writers edit the item, mob and recipe files by hand. Reading a file
+/// reports the entries that don't parse, validating a ContentSet reports what
+/// the game would trip over later, e.g. an attribute that isn't a number, a
+/// sprite no sheet has or two entries with the same name. Every error says
+/// which file, entry and field it is about.
+use crate::*;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Clone, PartialEq)]
+pub struct ContentError {
+    pub file: String,
+    /// Nothing when the whole file is bad
+    pub entry: Option<Entry>,
+    pub field: Option<String>,
+    pub message: String,
+}
+
+impl ContentError {
+    pub fn file(file: &str, message: String) -> ContentError {
+        ContentError {
+            file: file.to_string(),
+            entry: None,
+            field: None,
+            message,
+        }
+    }
+
+    pub fn entry(file: &str, entry: Entry, message: String) -> ContentError {
+        ContentError {
+            entry: Some(entry),
+            ..ContentError::file(file, message)
+        }
+    }
+
+    pub fn field(file: &str, entry: Entry, field: &str, message: String) -> ContentError {
+        ContentError {
+            field: Some(field.to_string()),
+            ..ContentError::entry(file, entry, message)
+        }
+    }
+}
+
+impl Debug for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Content Error: {}", self.file)?;
+
+        if let Some(entry) = &self.entry {
+            write!(f, " entry {}", entry)?;
+        }
+        if let Some(field) = &self.field {
+            write!(f, " field {}", field)?;
+        }
+
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl Display for ContentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl<T> Sourced<T> {
+    fn error(&self, field: &str, message: String) -> ContentError {
+        ContentError::field(&self.file, self.entry.clone(), field, message)
+    }
+}
+
+/// Every item, mob and recipe definition, read to be checked together
+#[derive(Clone, Debug, Default)]
+pub struct ContentSet {
+    pub items: Vec<Sourced<ItemDefinition>>,
+    pub mobs: Vec<Sourced<MobDefinition>>,
+    pub recipes: Vec<Sourced<RecipeDefinition>>,
+}
+
+impl ContentSet {
+    /// Read the content files in the folders, the entries that don't parse are
+    /// left out and returned as errors
+    pub fn load(items: &str, mobs: &str, recipes: &str) -> (ContentSet, Vec<ContentError>) {
+        let mut content = ContentSet::default();
+        let mut errors = Vec::new();
+
+        ContentSet::read(items, &mut content.items, &mut errors);
+        ContentSet::read(mobs, &mut content.mobs, &mut errors);
+        ContentSet::read(recipes, &mut content.recipes, &mut errors);
+
+        (content, errors)
+    }
+
+    fn read<T: Definition>(
+        path: &str,
+        definitions: &mut Vec<Sourced<T>>,
+        errors: &mut Vec<ContentError>,
+    ) {
+        for file in content_files(path) {
+            match FileReader::open(&file) {
+                Ok(reader) => {
+                    for entry in reader {
+                        match entry {
+                            Ok(definition) => definitions.push(definition),
+                            Err(err) => errors.push(err),
+                        }
+                    }
+                }
+                Err(err) => errors.push(err),
+            }
+        }
+    }
+
+    /// Everything wrong with the definitions, sprites are looked up in the
+    /// names given
+    pub fn validate(&self, sprites: &HashSet<String>) -> Vec<ContentError> {
+        let mut errors = Vec::new();
+
+        let item_ids = unique(
+            self.items.iter().map(|i| (i, i.definition.id.as_str())),
+            "id",
+            &mut errors,
+        );
+        unique(
+            self.items.iter().map(|i| (i, i.definition.name.as_str())),
+            "name",
+            &mut errors,
+        );
+        unique(
+            self.mobs.iter().map(|m| (m, m.definition.id.as_str())),
+            "id",
+            &mut errors,
+        );
+        unique(
+            self.mobs.iter().map(|m| (m, m.definition.name.as_str())),
+            "name",
+            &mut errors,
+        );
+        unique(
+            self.recipes.iter().map(|r| (r, r.definition.id.as_str())),
+            "id",
+            &mut errors,
+        );
+        unique(
+            self.recipes.iter().map(|r| (r, r.definition.name.as_str())),
+            "name",
+            &mut errors,
+        );
+
+        for item in self.items.iter() {
+            check_attributes(item, &item.definition.attributes, &mut errors);
+
+            let sprite = &item.definition.sprite;
+            if !sprite.is_empty() && !sprites.contains(sprite) {
+                errors.push(item.error("sprite", format!("there is no sprite named {}", sprite)));
+            }
+        }
+
+        for mob in self.mobs.iter() {
+            check_attributes(mob, &mob.definition.attributes, &mut errors);
+
+            let sprite = &mob.definition.sprite;
+            if sprite.is_empty() {
+                errors.push(mob.error("sprite", "a mob needs a sprite".into()));
+            } else if !sprites.contains(sprite) {
+                errors.push(mob.error("sprite", format!("there is no sprite named {}", sprite)));
+            }
+
+            for (i, id) in mob.definition.inventory.iter().enumerate() {
+                if !item_ids.contains(id.as_str()) {
+                    errors.push(mob.error(
+                        &format!("inventory[{}]", i),
+                        format!("there is no item with the id {}", id),
+                    ));
+                }
+            }
+        }
+
+        for recipe in self.recipes.iter() {
+            check_attributes(recipe, &recipe.definition.attributes, &mut errors);
+
+            if recipe.definition.ingredients.is_empty() {
+                errors.push(recipe.error("ingredients", "a recipe needs ingredients".into()));
+            }
+
+            for (i, ingredient) in recipe.definition.ingredients.iter().enumerate() {
+                let field = format!("ingredients[{}]", i);
+
+                if !item_ids.contains(ingredient.item.as_str()) {
+                    errors.push(recipe.error(
+                        &field,
+                        format!("there is no item with the id {}", ingredient.item),
+                    ));
+                }
+                if ingredient.amount == 0 {
+                    errors.push(recipe.error(&field, "the amount should be at least 1".into()));
+                }
+            }
+
+            if !item_ids.contains(recipe.definition.result.as_str()) {
+                errors.push(recipe.error(
+                    "result",
+                    format!("there is no item with the id {}", recipe.definition.result),
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+/// Report empty and repeated values of the field, returns the values seen
+fn unique<'a, T: 'a>(
+    entries: impl Iterator<Item = (&'a Sourced<T>, &'a str)>,
+    field: &str,
+    errors: &mut Vec<ContentError>,
+) -> HashSet<&'a str> {
+    let mut seen: HashMap<&str, &Sourced<T>> = HashMap::new();
+
+    for (entry, value) in entries {
+        if value.is_empty() {
+            errors.push(entry.error(field, format!("the {} is empty", field)));
+            continue;
+        }
+
+        match seen.get(value) {
+            Some(first) => errors.push(entry.error(
+                field,
+                format!(
+                    "{} is already used by {} entry {}",
+                    value, first.file, first.entry
+                ),
+            )),
+            None => {
+                seen.insert(value, entry);
+            }
+        }
+    }
+
+    seen.into_keys().collect()
+}
+
+fn check_attributes<T>(
+    entry: &Sourced<T>,
+    attributes: &[Attribute],
+    errors: &mut Vec<ContentError>,
+) {
+    for (i, attribute) in attributes.iter().enumerate() {
+        if let Err(err) = attribute.check() {
+            errors.push(entry.error(
+                &format!("attributes[{}]", i),
+                format!(
+                    "{} should be a {}, not {:?}",
+                    err.name, err.expected, err.value
+                ),
+            ));
+        }
+    }
+}
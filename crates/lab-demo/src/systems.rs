@@ -1,7 +1,6 @@
 use crate::*;
 use lab_builder::prelude::*;
 use lab_core::prelude::*;
-use lab_data::*;
 use lab_world::*;
 // move to a resources file of some sort.
 mod tiles {
@@ -13,185 +12,179 @@ mod tiles {
     pub const BRICK_WINDOW: &'static str = "brick_window";
     pub const BRICK_WINDOW_OPEN: &'static str = "brick_window_broken";
     //pub const NPC : &'static str = "npc_0";
-    pub const ITEM: &'static str = "item_50";
-    pub const ITEM2: &'static str = "item_15";
+    pub const CHAIR: &'static str = "chair";
+    pub const PLATE: &'static str = "tile";
+    pub const PUDDLE: &'static str = "gravel";
     pub const _LOCKED_DOOR: &'static str = "locked_door";
     pub const _ENEMY: &'static str = "mob_19";
 }
 
-/// Adds a simple map using the map builder for the purposes of a demo.
+/// Keys the demo interactions are registered under
+mod interactions {
+    pub use lab_world::keys::{BUMP, ITEM_PICKUP, LOCK_UNLOCK, NONE, SIGNAL_GATE};
+    pub const DOOR_OPEN: &'static str = "door.open";
+    pub const WINDOW_BREAK: &'static str = "window.break";
+    pub const NPC_ENEMY: &'static str = "npc.enemy";
+}
 
-pub fn create_simple_map_system(mut commands: Commands, mut palette: ResMut<TilePalette>) {
-    let _null_interaction = palette.add_interaction(TileInteraction {
-        caller: |_| TileInteractionResult::None.into(),
-        description: "Null",
-    });
+/// Items of the demo in the ItemCatalog, see resources/items
+mod items {
+    pub const BUILDING_2_KEY: &'static str = "building_2_key";
+    pub const MAGIC_RING: &'static str = "magic_ring";
+}
 
-    let bump_handle = palette.add_interaction(TileInteraction {
-        caller: |ctx| TileInteractionResult::Block(ctx.source).into(),
-        description: "Bump",
-    });
+/// Mobs of the demo in the MobCatalog, see resources/mobs
+mod mobs {
+    pub const DEMANDING_CUSTOMER: &'static str = "demanding_customer";
+    pub const CELLAR_TROLL: &'static str = "cellar_troll";
+}
 
-    let door_handle = palette.add_interaction(TileInteraction {
-        caller: |ctx| {
-            let comps = ctx
-                .world_catalog
-                .components
-                .get(tiles::BRICK_DOOR_OPEN)
-                .expect("Open brick door tile cannot be found");
+/// Locks in the demo and the keys that open them
+mod locks {
+    pub const BUILDING_2: &'static str = "building_2";
+}
 
-            TileInteractionResult::ChangeSprite(ctx.destination, comps.sprite.clone()).into()
-        },
-        description: "Open a door.",
-    });
+/// Channels the demo tiles are wired with
+mod signals {
+    pub const CHAIR_PLATE: &'static str = "chair_plate";
+}
 
-    let item_interaction = palette.add_interaction(TileInteraction {
-        description: "Get Item",
-        caller: |ctx| {
+/// Adds a simple map using the map builder for the purposes of a demo.
 
-            let definition = ctx
-                .interaction_query
-                .get::<ItemDefinition>(ctx.destination)
-                .unwrap();
-            let name = definition.name.clone();
+pub fn create_simple_map_system(
+    mut commands: Commands,
+    mut palette: ResMut<TilePalette>,
+    item_catalog: Res<ItemCatalog>,
+    mob_catalog: Res<MobCatalog>,
+) {
+    palette.register_interaction(
+        interactions::DOOR_OPEN,
+        TileInteraction {
+            caller: |ctx| {
+                let mut state = match ctx.interaction_query.get::<ObjectState>(ctx.destination) {
+                    Ok(state) => (*state).clone(),
+                    Err(_) => return TileInteractionResult::None.into(),
+                };
 
-            let item = ItemComponents {
-                name: Named(name.clone().into()),
-                weight: Weight(0.1),
-                item_type: ItemType::Key,
-                item_slot: ItemSlot::LeftHand,
-                handle: WorldHandle::default(),
-                description: (*definition).clone(),
-            };
-            // println!("{:?} interacted with {:?} for key ({:?})", ctx.source, ctx.destination, item.tile_handle);
-            let itype = ctx
-                .interaction_query
-                .get::<InteractableType>(ctx.source)
-                .ok();
+                let is_open = state.get_bool("open").unwrap_or(false);
 
-            if let Some(t) = itype {
-                if let InteractableType::Player = *t {
-                    return vec![
-                        TileInteractionResult::AddItem(ctx.source, item),
-                        TileInteractionResult::Despawn,
-                        TileInteractionResult::Message(format!("You picked up the {}", name).into()),
-                    ];
-                };
-            }
+                if is_open {
+                    return TileInteractionResult::None.into();
+                }
 
-            TileInteractionResult::None.into()
-        },
-    });
+                match ctx.interaction_type {
+                    // doors are opened on purpose, walking into one just bumps it
+                    InteractionType::Action(_) => {
+                        let comps = ctx
+                            .world_catalog
+                            .components
+                            .get(tiles::BRICK_DOOR_OPEN)
+                            .expect("Open brick door tile cannot be found");
 
-    let enemy_interaction = palette.add_interaction(TileInteraction {
-        caller: |ctx| {
-            let itype = ctx
-                .interaction_query
-                .get::<InteractableType>(ctx.source)
-                .ok();
+                        state.set_bool("open", true);
 
-            if let Some(t) = itype {
-                if let InteractableType::Player = *t {
-                    return vec![
+                        vec![
+                            TileInteractionResult::ChangeSprite(
+                                ctx.destination,
+                                comps.sprite.clone(),
+                            ),
+                            TileInteractionResult::ChangeState(ctx.destination, state),
+                        ]
+                    }
+                    InteractionType::Collision => vec![
+                        TileInteractionResult::Block(ctx.source),
                         TileInteractionResult::Message(
-                            "Hello, you are my enemy. Lets fight.".into(),
+                            "The door is closed, press E to open it.".into(),
                         ),
-                        TileInteractionResult::Block(ctx.source),
-                    ];
+                    ],
                 }
-            }
-            TileInteractionResult::None.into()
+            },
+            description: "Open a door.",
         },
-        description: "Enemy Interaction",
-    });
-    let locked_door_interaction = palette.add_interaction(TileInteraction {
-        caller: |ctx| {
-            let comps = ctx
-                .world_catalog
-                .components
-                .get(tiles::BRICK_DOOR_OPEN)
-                .expect("Open brick door tile cannot be found");
+    );
 
-            let inventory = ctx.interaction_query.get::<Inventory>(ctx.source).unwrap();
+    palette.register_interaction(
+        interactions::NPC_ENEMY,
+        TileInteraction {
+            caller: |ctx| {
+                let itype = ctx
+                    .interaction_query
+                    .get::<InteractableType>(ctx.source)
+                    .ok();
 
-            for e in (*inventory).0.iter() {
-                let entity = ctx.items.items.get(e).unwrap();
-
-                let item = ctx.item_query.get::<Named>(*entity).unwrap();
-
-                if item.0 == "Key To Building 2" {
-                    return vec![
-                        TileInteractionResult::ChangeSprite(ctx.destination, comps.sprite.clone()),
-                        TileInteractionResult::Message(
-                            "You have the key, Unlocked the door!".into(),
-                        ),
-                    ];
+                if let Some(t) = itype {
+                    if let InteractableType::Player = *t {
+                        return vec![
+                            TileInteractionResult::Message(
+                                "Hello, you are my enemy. Lets fight.".into(),
+                            ),
+                            TileInteractionResult::Block(ctx.source),
+                        ];
+                    }
                 }
-            }
-
-            vec![
-                TileInteractionResult::Block(ctx.source),
-                TileInteractionResult::Message(
-                    "The door is locked, maybe there's a key somewhere".into(),
-                ),
-            ]
+                TileInteractionResult::None.into()
+            },
+            description: "Enemy Interaction",
         },
-        description: "Open Door",
-    });
-    let window_interaction = palette.add_interaction(TileInteraction {
-        caller: |ctx| {
-            let comps = ctx
-                .world_catalog
-                .components
-                .get(tiles::BRICK_WINDOW_OPEN)
-                .expect("Open brick door tile cannot be found");
-            let itype = ctx
-                .interaction_query
-                .get::<InteractableType>(ctx.source)
-                .ok();
+    );
+    palette.register_interaction(
+        interactions::WINDOW_BREAK,
+        TileInteraction {
+            caller: |ctx| {
+                let itype = ctx
+                    .interaction_query
+                    .get::<InteractableType>(ctx.source)
+                    .ok();
 
-            // if a non-player hits a window, crash it if not block it
-            if let Some(source_type) = itype {
-                return match *source_type {
-                    InteractableType::Item | InteractableType::Npc => {
-                        TileInteractionResult::ChangeSprite(ctx.destination, comps.sprite.clone())
-                            .into()
-                    }
-                    _ => vec![
+                // if a non-player hits a window, crash it if not block it
+                if let Some(source_type) = itype {
+                    return match *source_type {
+                        InteractableType::Item | InteractableType::Npc => {
+                            TileInteractionResult::Damage(ctx.source, ctx.destination, 30.).into()
+                        }
+                        _ => vec![
+                            TileInteractionResult::Block(ctx.source),
+                            TileInteractionResult::Message(
+                                "The window looks breakable.".to_string(),
+                            ),
+                        ],
+                    };
+                } else {
+                    vec![
                         TileInteractionResult::Block(ctx.source),
                         TileInteractionResult::Message("The window looks breakable.".to_string()),
-                    ],
-                };
-            } else {
-                vec![
-                    TileInteractionResult::Block(ctx.source),
-                    TileInteractionResult::Message("The window looks breakable.".to_string()),
-                ]
-            }
+                    ]
+                }
+            },
+            description: "Break Window",
         },
-        description: "Break Window",
-    });
+    );
 
     if let Some(tiles) = palette.components.get_mut(tiles::WALL) {
         // walls are hard
-        tiles.state.set_int("hardness".into(), 1);
-        tiles.state.set_int("hit_points".into(), 800);
+        set_durability(&mut tiles.state, 800, 1);
     }
 
     if let Some(tiles) = palette.components.get_mut(tiles::BRICK) {
         // brick walls are beefier
-        tiles.state.set_int("hardness".into(), 5);
-        tiles.state.set_int("hit_points".into(), 1000);
+        set_durability(&mut tiles.state, 1000, 5);
     }
     if let Some(tiles) = palette.components.get_mut(tiles::BRICK_DOOR) {
-        tiles.state.set_int("hardness".into(), 1);
-        tiles.state.set_int("hit_points".into(), 100);
+        set_durability(&mut tiles.state, 100, 1);
+    }
+
+    if let Some(window) = palette.components.get_mut(tiles::BRICK_WINDOW) {
+        // windows crack before they give
+        set_durability(&mut window.state, 50, 0);
+        window.destructible = Destructible::default()
+            .stage(25, tiles::BRICK_WINDOW_OPEN)
+            .rubble(tiles::FLOOR);
     }
 
     if let Some(tiles) = palette.components.get(tiles::BRICK_DOOR) {
         // open doors
         let mut new_tile = tiles.clone();
-        new_tile.state.set_int("hardness".into(), 1);
+        new_tile.state.set_int(HARDNESS_STATE, 1);
     }
 
     let mut mb = MapBuilder::new(palette.clone(), &Location::default());
@@ -206,43 +199,43 @@ pub fn create_simple_map_system(mut commands: Commands, mut palette: ResMut<Tile
             RelativePosition::RightOf,
             5,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .add_interactable(
             RelativePosition::Below,
             5,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .add_interactable(
             RelativePosition::LeftOf,
             1,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .add_interactable(
             RelativePosition::LeftOf,
             1,
             tiles::BRICK_DOOR.to_string(),
-            door_handle,
+            interactions::DOOR_OPEN,
         )
         .add_interactable(
             RelativePosition::LeftOf,
             1,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .add_interactable(
             RelativePosition::LeftOf,
             2,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .add_interactable(
             RelativePosition::Above,
             5,
             tiles::WALL.to_string(),
-            bump_handle,
+            interactions::BUMP,
         )
         .to_blueprint("basic_house");
 
@@ -251,28 +244,52 @@ pub fn create_simple_map_system(mut commands: Commands, mut palette: ResMut<Tile
         Vec2::new(6., 6.),
         tiles::FLOOR.to_string(),
     )
-    .add_interactable(RelativePosition::RightOf, 5, tiles::BRICK.to_string(), bump_handle)
-    .add_interactable(RelativePosition::Below, 5, tiles::BRICK.to_string(), bump_handle)
+    .add_interactable(
+        RelativePosition::RightOf,
+        5,
+        tiles::BRICK.to_string(),
+        interactions::BUMP,
+    )
+    .add_interactable(
+        RelativePosition::Below,
+        5,
+        tiles::BRICK.to_string(),
+        interactions::BUMP,
+    )
     .add_interactable(
         RelativePosition::LeftOf,
         2,
         tiles::BRICK_WINDOW.to_string(),
-        window_interaction,
+        interactions::WINDOW_BREAK,
     )
     .add_interactable(
         RelativePosition::LeftOf,
         1,
         tiles::BRICK_DOOR.to_string(),
-        locked_door_interaction,
+        interactions::LOCK_UNLOCK,
     )
+    .with_lock(Lock {
+        key_id: locks::BUILDING_2.into(),
+        consume_key: false,
+        open_tile: Some(tiles::BRICK_DOOR_OPEN.into()),
+    })
     .add_interactable(
         RelativePosition::LeftOf,
         1,
         tiles::BRICK_WINDOW.to_string(),
-        window_interaction,
+        interactions::WINDOW_BREAK,
     )
     .add_tiles(RelativePosition::LeftOf, 1, tiles::BRICK.to_string())
-    .add_tiles(RelativePosition::Above, 5, tiles::BRICK.to_string())
+    .add_tiles(RelativePosition::Above, 2, tiles::BRICK.to_string())
+    // a way out the back that looks like the rest of the wall
+    .add_interactable(
+        RelativePosition::Above,
+        1,
+        tiles::BRICK_DOOR_OPEN.to_string(),
+        interactions::NONE,
+    )
+    .with_concealment(Concealment::new(tiles::BRICK, 12))
+    .add_tiles(RelativePosition::Above, 2, tiles::BRICK.to_string())
     .to_blueprint("brick_house");
 
     mb.add_tiles_to_area(
@@ -287,27 +304,62 @@ pub fn create_simple_map_system(mut commands: Commands, mut palette: ResMut<Tile
         .add_tiles_from_blueprint("walkway")
         .add_tiles_from_blueprint("basic_house")
         .set_position(Location(-32., 0., 3., WorldLocation::World))
-        .add_item(
+        .add_catalog_item(
             RelativePosition::Below,
             1,
-            tiles::ITEM.to_string(),
-            item_interaction,
-            ItemDefinition {
-                name: "Key To Building 2".into(),
-                ..Default::default()
-            },
+            &item_catalog,
+            items::BUILDING_2_KEY,
         )
-        .add_item(
+        .add_catalog_item(RelativePosition::Below, 1, &item_catalog, items::MAGIC_RING)
+        .add_pushable(RelativePosition::Below, 1, tiles::CHAIR.to_string())
+        // push the chair onto the plate to open the door below it
+        .add_interactable(
             RelativePosition::Below,
             1,
-            tiles::ITEM2.to_string(),
-            item_interaction,
-            ItemDefinition {
-                name: "Magic Ring".into(),
-                ..Default::default()
-            },
+            tiles::PLATE.to_string(),
+            interactions::NONE,
+        )
+        .with_wiring(SignalWiring::source(
+            SignalKind::Plate,
+            signals::CHAIR_PLATE,
+        ))
+        .add_interactable(
+            RelativePosition::Below,
+            1,
+            tiles::BRICK_DOOR.to_string(),
+            interactions::SIGNAL_GATE,
+        )
+        .with_wiring(
+            SignalWiring::listener(SignalLogic::Or, &[signals::CHAIR_PLATE])
+                .with_tiles(tiles::BRICK_DOOR_OPEN, tiles::BRICK_DOOR),
+        )
+        // spilled beer, and not all of the floor next to it is what it seems
+        .set_position(Location(64., -96., 2., WorldLocation::World))
+        .add_hazard(
+            RelativePosition::Current,
+            1,
+            tiles::PUDDLE.to_string(),
+            Hazard::new(HazardKind::SpilledBeer, HazardTrigger::Step),
+        )
+        .add_hazard(
+            RelativePosition::Below,
+            1,
+            tiles::FLOOR.to_string(),
+            Hazard::new(HazardKind::Spikes, HazardTrigger::Step).hidden(15),
         );
-    //.add_mobs(Location(-32.,64.,3., WorldLocation::World), 10,  tiles::ENEMY.to_string());
+
+    mb.add_mobs(
+        Location(-32., 64., 3., WorldLocation::World),
+        2,
+        &mob_catalog,
+        mobs::DEMANDING_CUSTOMER,
+    )
+    .add_mobs(
+        Location(96., -160., 3., WorldLocation::World),
+        1,
+        &mob_catalog,
+        mobs::CELLAR_TROLL,
+    );
     //.add_tiles_from_blueprint("walkway");*/
     //.add_tiles_from_blueprint("basic_house_2");
 
@@ -321,19 +373,28 @@ pub fn create_simple_map_system(mut commands: Commands, mut palette: ResMut<Tile
     }
 
     for mob in mb.mobs.iter().cloned() {
-        commands
-            .spawn(mob.clone())
-            .with_bundle(mob.sprite.to_components(mob.location.into(), 1.))
-            .with_bundle(Interactable::new(InteractableType::Npc))
-            .with(enemy_interaction);
+        spawn_mob(&mut commands, mob);
     }
     for (comps, item) in mb.items.iter() {
         commands
             .spawn(comps.clone())
             .with(item.clone())
             .with_bundle(comps.sprite.to_components(comps.location.into(), 1.))
-            .with_bundle(Interactable::new(InteractableType::Item))
-            .with(item_interaction);
+            .with_bundle(Interactable::new(InteractableType::Item));
+    }
+    for (comps, hazard) in mb.hazards.iter() {
+        commands
+            .spawn(comps.clone())
+            .with(hazard.clone())
+            .with_bundle(comps.sprite.to_components(comps.location.into(), 1.))
+            .with_bundle(Interactable::new(InteractableType::Tile));
+    }
+    for comps in mb.pushables.iter() {
+        commands
+            .spawn(comps.clone())
+            .with_bundle(comps.sprite.to_components(comps.location.into(), 1.))
+            .with_bundle(Interactable::new(InteractableType::Tile))
+            .with_bundle((Moveable, Movement::default()));
     }
 
     //commands.spawn((Moveable, Location(TILE_SIZE*2.,TILE_SIZE*2.,2.), Visible));
@@ -1,6 +1,12 @@
+pub use lab_data::Behavior;
+
 #[allow(dead_code)]
 struct Interactable;
 #[allow(dead_code)]
 struct Seller;
 #[allow(dead_code)]
 struct Buyer;
+
+/// Items, by id in the ItemCatalog, a mob is given when it spawns
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MobLoot(pub Vec<String>);
@@ -1,5 +1,7 @@
 use bevy::prelude::*;
 use lab_core::prelude::*;
+use lab_data::Attribute;
+use serde::{Deserialize, Serialize};
 use std::time::Duration;
 
 #[derive(Clone, Copy, Debug, Properties)]
@@ -25,11 +27,15 @@ pub struct PlayerComponents {
     location: Location,
     movement: Movement,
     zoomable: Zoomable,
+    facing: Facing,
+    speed: Speed,
+    health: Health,
+    level: Level,
     input_timer: InputTimer
 }
 
 impl PlayerComponents {
-    pub fn new(name: &'static str) -> PlayerComponents {
+    pub fn new(name: &str) -> PlayerComponents {
         PlayerComponents {
             named: Named(String::from(name)),
             ..Default::default()
@@ -51,11 +57,15 @@ impl Default for PlayerComponents {
             input_timer: InputTimer(Timer::new(Duration::from_millis(100), false)),
             movement: Movement::default(),
             zoomable: Zoomable,
+            facing: Facing::default(),
+            speed: Speed::default(),
+            health: Health::default(),
+            level: Level::default(),
         }
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Properties)]
+#[derive(Debug, Default, Clone, PartialEq, Properties, Serialize, Deserialize)]
 pub struct Stats {
     strength: u32,
     dextarity: u32,
@@ -65,13 +75,13 @@ pub struct Stats {
     charisma: u32,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Properties)]
+#[derive(Debug, Default, Clone, PartialEq, Properties, Serialize, Deserialize)]
 pub struct Abilities {
     magic_power: u32,
     brewing_power: u32,
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Properties)]
+#[derive(Debug, Default, Clone, PartialEq, Properties, Serialize, Deserialize)]
 pub struct Skills {
     brewing: u32,
     stealth: u32,
@@ -94,6 +104,26 @@ impl Skills {
             ..Default::default()
         }
     }
+
+    pub fn brewing(&self) -> u32 {
+        self.brewing
+    }
+
+    pub fn stealth(&self) -> u32 {
+        self.stealth
+    }
+
+    pub fn melee(&self) -> u32 {
+        self.melee
+    }
+
+    pub fn throwing(&self) -> u32 {
+        self.throwing
+    }
+
+    pub fn diplomacy(&self) -> u32 {
+        self.diplomacy
+    }
 }
 
 impl Stats {
@@ -102,9 +132,98 @@ impl Stats {
             ..Default::default()
         }
     }
+
+    pub fn strength(&self) -> u32 {
+        self.strength
+    }
+
+    pub fn dextarity(&self) -> u32 {
+        self.dextarity
+    }
+
+    pub fn wit(&self) -> u32 {
+        self.wit
+    }
+
+    pub fn creativity(&self) -> u32 {
+        self.creativity
+    }
+
+    pub fn wisdom(&self) -> u32 {
+        self.wisdom
+    }
+
+    pub fn charisma(&self) -> u32 {
+        self.charisma
+    }
+
+    /// Stats named by the attributes, e.g. strength, the rest are left at zero
+    pub fn from_attributes(attributes: &[Attribute]) -> Stats {
+        let mut stats = Stats::new();
+
+        for attribute in attributes {
+            let stat = match attribute.name() {
+                "strength" => &mut stats.strength,
+                "dextarity" => &mut stats.dextarity,
+                "wit" => &mut stats.wit,
+                "creativity" => &mut stats.creativity,
+                "wisdom" => &mut stats.wisdom,
+                "charisma" => &mut stats.charisma,
+                _ => continue,
+            };
+
+            match attribute.value().parse() {
+                Ok(value) => *stat = value,
+                Err(_) => log::warn!(
+                    "Stat {} should be a number, not {}",
+                    attribute.name(),
+                    attribute.value()
+                ),
+            }
+        }
+
+        stats
+    }
+}
+
+/// Hit points of players and mobs, tiles keep theirs in their ObjectState
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health::from_stats(&Stats::new())
+    }
+}
+
+impl Health {
+    /// Hit points of an actor without any strength
+    pub const BASE: i32 = 10;
+
+    pub fn new(max: i32) -> Health {
+        Health { current: max, max }
+    }
+
+    /// Stronger actors can take more of a beating
+    pub fn from_stats(stats: &Stats) -> Health {
+        Health::new(Health::BASE + stats.strength() as i32)
+    }
+
+    /// Take the damage, returns the hit points left
+    pub fn hurt(&mut self, amount: i32) -> i32 {
+        self.current = (self.current - amount).max(0);
+        self.current
+    }
+
+    pub fn is_down(&self) -> bool {
+        self.current == 0
+    }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub enum Job {
     BeerWizard,
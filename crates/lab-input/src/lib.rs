@@ -13,6 +13,7 @@ pub mod prelude {
 impl Plugin for InputPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_event::<MouseClickEvent>()
+            .add_event::<PlayerActionEvent>()
             .init_resource::<SelectedTile>()
             .init_resource::<State>()
             .init_resource::<ScrollState>()
@@ -25,7 +26,8 @@ impl Plugin for InputPlugin {
                 systems::track_mouse_movement_system.system(),
             )
             .add_system_to_stage(stages::PRE_UPDATE, systems::mouse_wheel_system.system())
-            .add_system_to_stage(stages::PRE_UPDATE, systems::mouse_click_system.system());
+            .add_system_to_stage(stages::PRE_UPDATE, systems::mouse_click_system.system())
+            .add_system_to_stage(stages::PRE_UPDATE, systems::player_action_system.system());
     }
 }
 
@@ -88,6 +90,8 @@ pub struct Mouse {
     pub position: Vec2,
     pub ui_position: Vec2,
     pub world_position: Vec3,
+    /// The tile under the mouse, at the current zoom
+    pub tile: TileCoord,
 }
 
 pub struct MouseClickEvent {
@@ -101,3 +105,17 @@ pub struct MouseClickEvent {
 pub struct MouseState {
     pub click_events: EventReader<MouseClickEvent>,
 }
+
+pub mod actions {
+    /// Use whatever the player is facing, e.g. open a door or pull a lever
+    pub const USE: &'static str = "use";
+    /// Look around for anything hidden nearby
+    pub const SEARCH: &'static str = "search";
+}
+
+/// Sent when the player deliberately acts, the world decides what is acted on
+#[derive(Clone, Debug)]
+pub struct PlayerActionEvent {
+    pub entity: Entity,
+    pub action: String,
+}
@@ -41,37 +41,30 @@ pub fn track_mouse_movement_system(
     windows: Res<Windows>,
     mut camera_query: Query<(&Camera, &Transform)>,
 ) {
-    let mut camera_offset_x: f32 = 0.;
-    let mut camera_offset_y: f32 = 0.;
+    let mut camera_transform = Transform::identity();
 
     for (c, t) in &mut camera_query.iter() {
         if *(c.name.as_ref()).unwrap_or(&"".to_string()) != "UiCamera" {
-            camera_offset_x = t.translation().x();
-            camera_offset_y = t.translation().y();
+            camera_transform = *t;
         }
     }
 
     let window = windows.iter().last().unwrap();
-    let x_window_offset = window.width;
-    let y_window_offset = window.height;
+    let tile = world.tile(scroll_state.current_scale);
 
     for event in state.cursor_moved_event_reader.iter(&cursor_moved_events) {
-        let mut normalized_x = event.position.x() + camera_offset_x - (x_window_offset / 2) as f32;
-        let mut normalized_y = event.position.y() + camera_offset_y - (y_window_offset / 2) as f32;
+        let position = Location::normalize(window, &camera_transform, &event.position);
 
         // snap to grid
+        mouse.tile = TileCoord::from_world(position, tile);
 
-        let grid_x = normalized_x / (world.tile_size * scroll_state.current_scale);
-        let grid_y = normalized_y / (world.tile_size * scroll_state.current_scale);
-
-        normalized_x = grid_x.round() * world.tile_size * scroll_state.current_scale;
-        normalized_y = grid_y.round() * world.tile_size * scroll_state.current_scale;
+        let snapped = mouse.tile.to_world(tile);
 
         // backwards compat
-        mouse.position = Vec2::new(normalized_x, normalized_y);
+        mouse.position = snapped;
 
         // fields that give us both ui and world positions
-        mouse.world_position = Vec3::new(normalized_x, normalized_y, 0.);
+        mouse.world_position = snapped.extend(0.);
         mouse.ui_position = event.position.clone();
 
         log::trace!("Mouse position: {:?}", *mouse);
@@ -104,10 +97,16 @@ pub fn mouse_click_system(
     };
 }
 
+/// Moves the player with W,A,S,D, continuously or a tile at a time depending on
+/// the movement mode in the WorldSettings.
 pub fn player_movement_system(
+    mut commands: Commands,
     time: Res<Time>,
+    settings: Res<WorldSettings>,
     keyboard_input: Res<Input<KeyCode>>,
+    step_query: Query<&GridStep>,
     mut query: Query<(
+        Entity,
         &player::Player,
         &mut Transform,
         &mut Movement,
@@ -115,6 +114,7 @@ pub fn player_movement_system(
         &mut TextureAtlasSprite,
         &mut lab_core::InputTimer,
         &mut Handle<TextureAtlas>,
+        &mut Facing,
     )>,
 ) {
     let mut anination_direction = CardinalDirection::None;
@@ -142,6 +142,7 @@ pub fn player_movement_system(
 
     if anination_direction != CardinalDirection::None {
         for (
+            entity,
             _player,
             mut transform,
             mut movement,
@@ -149,14 +150,35 @@ pub fn player_movement_system(
             mut texture_sprite,
             mut timer,
             mut atlas,
+            mut facing,
         ) in &mut query.iter()
         {
+            facing.0 = anination_direction;
             timer.0.tick(time.delta_seconds);
             let trans = transform.translation();
 
             let scale = transform.scale();
-            transform.set_translation(trans + time.delta_seconds * (direction * scale) * 80.0);
-            *movement = Movement::new(trans, transform.translation(), direction);
+            match settings.movement() {
+                MovementMode::Free => {
+                    transform
+                        .set_translation(trans + time.delta_seconds * (direction * scale) * 80.0);
+                    *movement = Movement::new(trans, transform.translation(), direction);
+                }
+                // held keys take the next step once the last one is done
+                MovementMode::Grid if step_query.get::<GridStep>(entity).is_err() => {
+                    let step = anination_direction.vec2().extend(0.);
+                    let tile = settings.tile(scale.x());
+                    let to = TileCoord::from_world(trans.truncate(), tile)
+                        .neighbor(anination_direction)
+                        .to_world(tile)
+                        .extend(trans.z());
+
+                    *movement = Movement::new(trans, to, step);
+                    commands.insert_one(entity, GridStep::new(trans, to, settings.step_seconds));
+                }
+                MovementMode::Grid => {}
+            }
+
             if timer.0.finished {
                 let sprite = match anination_direction {
                     CardinalDirection::North => {
@@ -187,3 +209,25 @@ pub fn player_movement_system(
         }
     }
 }
+
+/// Send a use action for the player when E is pressed, a search when F is
+pub fn player_action_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut events: ResMut<Events<PlayerActionEvent>>,
+    mut query: Query<With<player::Player, Entity>>,
+) {
+    let action = if keyboard_input.just_pressed(KeyCode::E) {
+        actions::USE
+    } else if keyboard_input.just_pressed(KeyCode::F) {
+        actions::SEARCH
+    } else {
+        return;
+    };
+
+    for entity in &mut query.iter() {
+        events.send(PlayerActionEvent {
+            entity,
+            action: action.to_string(),
+        });
+    }
+}
@@ -5,10 +5,13 @@ use std::{
     time::Duration,
 };
 
-use lab_core::stages;
+use lab_core::{stages, Collider};
 
+mod sheets;
 mod systems;
 
+pub use sheets::{sprite_label, sprite_names, SpriteSheet, SPRITE_SHEETS};
+
 pub struct SpritesPlugin;
 
 impl Plugin for SpritesPlugin {
@@ -55,6 +58,11 @@ impl SpriteInfo {
     pub fn scaled_size(&self, scale: f32) -> Vec3 {
         return Vec3::new(self.width as f32 * scale, self.height as f32 * scale, 0.);
     }
+
+    /// Collider covering the whole sprite
+    pub fn collider(&self) -> Collider {
+        Collider::new(self.size())
+    }
 }
 
 pub struct Letter;
@@ -135,14 +143,9 @@ impl SpriteLibrary {
         let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
         for i in 0..(dim.0 * dim.1) {
-            // allow for single label naming. if ["name"] is passed, the sprites will be labeled name_{idx}
-            let name = if labels.len() == 1 {
-                format!("{}_{}", labels[0].to_string(), i).to_string()
-            } else {
-                if i >= labels.len() {
-                    break;
-                }
-                labels[i].to_string()
+            let name = match sprite_label(labels, i) {
+                Some(name) => name,
+                None => break,
             };
             //println!("Adding sprite named {}",name);
 
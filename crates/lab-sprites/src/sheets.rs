@@ -0,0 +1,118 @@
+/// Module for the sprite sheets the game loads
+///
+/// The table is plain data so the names of the sprites are known without
+/// loading a texture, the content checks look sprite names up in it.
+use std::collections::HashSet;
+
+pub struct SpriteSheet {
+    pub filename: &'static str,
+    /// One label per sprite, or a single label the sprites are numbered after
+    pub labels: &'static [&'static str],
+    /// Columns and rows of the sheet
+    pub dim: (usize, usize),
+    pub category: &'static str,
+}
+
+impl SpriteSheet {
+    /// Names of the sprites on the sheet, in the order they are cataloged
+    pub fn names(&self) -> Vec<String> {
+        (0..(self.dim.0 * self.dim.1))
+            .filter_map(|i| sprite_label(self.labels, i))
+            .collect()
+    }
+}
+
+/// Name of the sprite at the index, if ["name"] is passed the sprites are
+/// labeled name_{idx}
+pub fn sprite_label(labels: &[&str], i: usize) -> Option<String> {
+    if labels.len() == 1 {
+        Some(format!("{}_{}", labels[0], i))
+    } else {
+        labels.get(i).map(|label| label.to_string())
+    }
+}
+
+/// Every sprite name the SpriteLibrary will hold
+pub fn sprite_names() -> HashSet<String> {
+    SPRITE_SHEETS
+        .iter()
+        .flat_map(|sheet| sheet.names())
+        .collect()
+}
+
+pub const SPRITE_SHEETS: &[SpriteSheet] = &[
+    SpriteSheet {
+        filename: "resources/sprites/world.png",
+        labels: &[
+            "gravel",
+            "wall",
+            "floor",
+            "tile",
+            "gravel_h",
+            "brick",
+            "brick_door_closed",
+            "chair",
+            "gravel_v",
+            "brick_window",
+            "brick_door_open",
+            "shelf",
+            "brick_window_broken",
+            "bed",
+            "table",
+            "fridge",
+        ],
+        dim: (4, 4),
+        category: "world",
+    },
+    SpriteSheet {
+        filename: "resources/sprites/roguelikecreatures.png",
+        labels: &["mob"],
+        dim: (8, 9),
+        category: "mobs",
+    },
+    SpriteSheet {
+        filename: "resources/sprites/roguelikeitems.png",
+        labels: &["item"],
+        dim: (13, 14),
+        category: "items",
+    },
+    SpriteSheet {
+        filename: "resources/sprites/walk_left.png",
+        labels: &["walk_left"],
+        dim: (13, 1),
+        category: "walk_left",
+    },
+    SpriteSheet {
+        filename: "resources/sprites/walk_right.png",
+        labels: &["walk_right"],
+        dim: (13, 1),
+        category: "walk_right",
+    },
+    SpriteSheet {
+        filename: "resources/fonts/alphabet.png",
+        labels: &[
+            " ", "!", "\"", "#", "$", "%", "&", "'", "(", ")", "*", "+", ",", "-", ".", "/", "0",
+            "1", "2", "3", "4", "5", "6", "7", "8", "9", ":", ";", "<", "=", ">", "?", "@", "a",
+            "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r",
+            "s", "t", "u", "v", "w", "x", "y", "z",
+        ],
+        dim: (15, 8),
+        category: "letters",
+    },
+    SpriteSheet {
+        filename: "resources/sprites/dungeon_tiles.png",
+        labels: &[
+            "dw_right_top",
+            "dw_center_top",
+            "dw_left_top",
+            "dw_left",
+            "dw_center",
+            "dw_right",
+            "dw_right_bottom",
+            "dw_right_center",
+            "dw_left_bottom",
+        ],
+        dim: (3, 3),
+        category: "dungeon",
+    },
+];
@@ -1,7 +1,7 @@
 use bevy::prelude::*;
 use bevy_tiled::{Map};
-use lab_core::{InteractableType, Inventory, ObjectState};
-use lab_world::{TileInteraction, TileInteractionResult};
+use lab_core::{Collider, InteractableType, InteractionKey, Inventory, ObjectState};
+use lab_world::keys;
 use std::collections::{HashMap, HashSet};
 use tiled::Object;
 pub struct TiledDemoPlugin;
@@ -44,8 +44,8 @@ pub struct ObjectComponents {
     object : Object,
     transform : Transform,
     interaction_type: InteractableType,
-    interaction: TileInteraction,
-    size: Size,
+    interaction: InteractionKey,
+    collider: Collider,
     inventory : Inventory,
     object_state: ObjectState
 }
@@ -81,17 +81,16 @@ fn register_objects(mut commands : Commands,
                 for object in &og.objects {
                     commands
                         .spawn(ObjectComponents {
-                            interaction: TileInteraction {
-                                caller: |ctx| TileInteractionResult::Block(ctx.source).into(),
-                                description: "Bump",
-                            },
+                            interaction: InteractionKey::new(keys::BUMP),
                             object: object.clone(),
                             transform: Transform::from_translation(Vec3::new(
                                 object.x, 
                                 -object.y, 
                                 og.layer_index.unwrap_or_else(|| 0) as f32)),
                             interaction_type: InteractableType::Tile,
-                            size: Size::new(object.width, object.height),
+                            // objects start at their top left corner
+                            collider: Collider::new(Vec2::new(object.width, object.height))
+                                .with_offset(Vec2::new(object.width / 2., -object.height / 2.)),
                             inventory: Inventory::default(),
                             object_state: ObjectState::default()
                         }).for_current_entity(|e| {
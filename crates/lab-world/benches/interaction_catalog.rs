@@ -0,0 +1,112 @@
+/// Compares copying the TilePalette for every interaction event against
+/// borrowing it, the way InteractionContext used to and now does.
+///
+/// Run with `cargo bench -p lab-world --bench interaction_catalog`
+use lab_core::prelude::*;
+use lab_sprites::SpriteInfo;
+use lab_world::*;
+use std::time::{Duration, Instant};
+
+/// Roughly the number of sprites in the sprite sheets
+const PALETTE_SIZE: usize = 1500;
+/// NPCs bumping into walls in the same frame
+const COLLISIONS: usize = 300;
+const FRAMES: usize = 60;
+
+fn palette() -> TilePalette {
+    let mut palette = TilePalette::default();
+
+    register_builtin_interactions(&mut palette);
+
+    for i in 0..PALETTE_SIZE {
+        let name = format!("sprite_{}", i);
+
+        palette.components.insert(
+            name.clone(),
+            TileComponents {
+                name: Named(name.clone()),
+                sprite: SpriteInfo {
+                    name,
+                    atlas_sprite: i as u32,
+                    width: 16,
+                    height: 16,
+                    category: format!("category_{}", i % 12),
+                    ..Default::default()
+                },
+                interaction: InteractionKey::new(keys::BUMP),
+                ..Default::default()
+            },
+        );
+    }
+
+    palette
+}
+
+/// What an interaction does with the catalog, look up a tile and its interaction
+fn interact(catalog: &TilePalette, collision: usize) -> usize {
+    let tile = catalog
+        .components
+        .get(&format!("sprite_{}", collision % PALETTE_SIZE))
+        .expect("Tile is in the palette");
+
+    match catalog.get_interaction_by_key(&tile.interaction) {
+        Some(_) => tile.sprite.atlas_sprite as usize,
+        None => 0,
+    }
+}
+
+fn cloned(palette: &TilePalette) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut total = 0;
+
+    for _ in 0..FRAMES {
+        for collision in 0..COLLISIONS {
+            let catalog = palette.clone();
+
+            total += interact(&catalog, collision);
+        }
+    }
+
+    (start.elapsed(), total)
+}
+
+fn borrowed(palette: &TilePalette) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut total = 0;
+
+    for _ in 0..FRAMES {
+        for collision in 0..COLLISIONS {
+            total += interact(palette, collision);
+        }
+    }
+
+    (start.elapsed(), total)
+}
+
+fn main() {
+    let palette = palette();
+
+    let (clone_time, clone_total) = cloned(&palette);
+    let (borrow_time, borrow_total) = borrowed(&palette);
+
+    assert_eq!(clone_total, borrow_total);
+
+    println!(
+        "{} palette tiles, {} collisions per frame, {} frames",
+        PALETTE_SIZE, COLLISIONS, FRAMES
+    );
+    println!(
+        "clone per event: {:?} ({:?} per frame)",
+        clone_time,
+        clone_time / FRAMES as u32
+    );
+    println!(
+        "borrow:          {:?} ({:?} per frame)",
+        borrow_time,
+        borrow_time / FRAMES as u32
+    );
+    println!(
+        "speed up:        {:.1}x",
+        clone_time.as_secs_f64() / borrow_time.as_secs_f64()
+    );
+}
@@ -0,0 +1,178 @@
+/// Module for secret doors and passages
+///
+/// A concealed tile is drawn as its disguise and blocks like a wall until a
+/// player finds it. Searching looks a couple of tiles around the player,
+/// walking up to one gives a harder passive check. Both roll against the
+/// wisdom and stealth of the player, it takes a sneak to know one.
+use crate::{TileInteractionResult, TileInteractionResultEvent, TilePalette};
+use lab_core::prelude::*;
+use lab_entities::prelude::*;
+use lab_input::{actions, PlayerActionEvent};
+use lab_sprites::SpriteInfo;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Added to the difficulty when a player isn't looking on purpose
+pub const PASSIVE_PENALTY: u32 = 5;
+/// Tiles around the player a search reaches
+pub const SEARCH_RANGE: f32 = 2.;
+
+/// The default concealment hides nothing
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Concealment {
+    pub hidden: bool,
+    /// Tile in the palette to draw while hidden
+    #[serde(default)]
+    pub disguise: Option<String>,
+    /// What a search roll has to reach to find it
+    #[serde(default)]
+    pub difficulty: u32,
+}
+
+impl Concealment {
+    pub fn new(disguise: &str, difficulty: u32) -> Concealment {
+        Concealment {
+            hidden: true,
+            disguise: Some(disguise.to_string()),
+            difficulty,
+        }
+    }
+}
+
+/// A d20 plus the wisdom and stealth of the player against the difficulty
+pub fn finds(stats: &Stats, skills: &Skills, difficulty: u32) -> bool {
+    rand::thread_rng().gen_range(1, 21) + stats.wisdom() + skills.stealth() >= difficulty
+}
+
+#[derive(Default)]
+pub struct ConcealmentState {
+    pub action_events: EventReader<PlayerActionEvent>,
+    /// Players next to each hidden tile last frame, they only get one look
+    pub nearby: HashMap<Entity, Vec<Entity>>,
+    /// Hidden tiles already drawn as their disguise
+    pub disguised: HashSet<Entity>,
+}
+
+/// The bounds grown by a number of tiles on every side
+fn around(bounds: &Bounds, tiles: f32) -> Bounds {
+    Bounds::from_center(bounds.center(), bounds.size() * (1. + tiles * 2.))
+}
+
+/// Concealment System
+/// Draws hidden tiles as their disguise and reveals them to players that
+/// search for them or walk up to them.
+pub fn concealment_system(
+    mut commands: Commands,
+    mut state: Local<ConcealmentState>,
+    action_events: Res<Events<PlayerActionEvent>>,
+    mut results: ResMut<Events<TileInteractionResultEvent>>,
+    index: Res<SpatialIndex>,
+    palette: Res<TilePalette>,
+    player_query: Query<(&Player, &Stats, &Skills)>,
+    mut hidden_query: Query<(Entity, &SpriteInfo, &mut Concealment)>,
+) {
+    let mut searching: Vec<Entity> = state
+        .action_events
+        .iter(&action_events)
+        .filter(|event| event.action == actions::SEARCH)
+        .map(|event| event.entity)
+        .collect();
+
+    let mut found_by = Vec::new();
+
+    for (entity, sprite, mut concealment) in &mut hidden_query.iter() {
+        if !concealment.hidden {
+            continue;
+        }
+
+        if state.disguised.insert(entity) {
+            if let Some(disguise) = concealment
+                .disguise
+                .as_ref()
+                .and_then(|name| palette.components.get(name))
+            {
+                commands.insert(
+                    entity,
+                    (
+                        TextureAtlasSprite::new(disguise.sprite.atlas_sprite),
+                        disguise.sprite.atlas_handle,
+                    ),
+                );
+            }
+        }
+
+        let bounds = match index.get(entity) {
+            Some(bounds) => *bounds,
+            None => continue,
+        };
+
+        let finder = |player: &Entity, difficulty: u32| match (
+            player_query.get::<Stats>(*player),
+            player_query.get::<Skills>(*player),
+        ) {
+            (Ok(stats), Ok(skills)) => finds(&stats, &skills, difficulty),
+            _ => false,
+        };
+
+        let searched = index
+            .query_aabb(&around(&bounds, SEARCH_RANGE))
+            .into_iter()
+            .filter(|e| searching.contains(e))
+            .find(|player| finder(player, concealment.difficulty));
+
+        let nearby: Vec<Entity> = index
+            .query_aabb(&around(&bounds, 1.))
+            .into_iter()
+            .filter(|e| player_query.get::<Player>(*e).is_ok())
+            .collect();
+
+        let before = state
+            .nearby
+            .insert(entity, nearby.clone())
+            .unwrap_or_default();
+
+        let noticed = searched.or_else(|| {
+            nearby
+                .into_iter()
+                .filter(|e| !before.contains(e))
+                .find(|player| finder(player, concealment.difficulty + PASSIVE_PENALTY))
+        });
+
+        let player = match noticed {
+            Some(player) => player,
+            None => continue,
+        };
+
+        concealment.hidden = false;
+
+        state.disguised.remove(&entity);
+        state.nearby.remove(&entity);
+
+        commands.insert(
+            entity,
+            (
+                TextureAtlasSprite::new(sprite.atlas_sprite),
+                sprite.atlas_handle,
+            ),
+        );
+
+        found_by.push(player);
+
+        results.send(TileInteractionResultEvent::new(
+            player,
+            entity,
+            TileInteractionResult::Message("You found a hidden passage!".into()),
+        ));
+    }
+
+    searching.retain(|player| !found_by.contains(player));
+
+    for player in searching {
+        results.send(TileInteractionResultEvent::new(
+            player,
+            player,
+            TileInteractionResult::Message("You search around but find nothing.".into()),
+        ));
+    }
+}
@@ -0,0 +1,69 @@
+/// Module for tiles that can be broken
+///
+/// A tile can be damaged when its ObjectState has hit points, hardness is
+/// taken off every hit. The Destructible component decides what the tile
+/// looks like as it breaks and what is left when it is destroyed.
+use lab_core::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// ObjectState key holding the hit points left
+pub const HIT_POINTS_STATE: &'static str = "hit_points";
+/// ObjectState key holding how much damage every hit loses
+pub const HARDNESS_STATE: &'static str = "hardness";
+
+/// Tile to show once the hit points drop to or below the value
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DamageStage {
+    pub hit_points: i32,
+    pub tile: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Destructible {
+    #[serde(default)]
+    pub stages: Vec<DamageStage>,
+    /// Tile left behind when destroyed, without one the tile is removed
+    #[serde(default)]
+    pub rubble: Option<String>,
+}
+
+impl Destructible {
+    pub fn stage(mut self, hit_points: i32, tile: &str) -> Self {
+        self.stages.push(DamageStage {
+            hit_points,
+            tile: tile.to_string(),
+        });
+        self
+    }
+
+    pub fn rubble(mut self, tile: &str) -> Self {
+        self.rubble = Some(tile.to_string());
+        self
+    }
+
+    /// The most damaged stage reached at the hit points
+    pub fn stage_at(&self, hit_points: i32) -> Option<&DamageStage> {
+        self.stages
+            .iter()
+            .filter(|stage| hit_points <= stage.hit_points)
+            .min_by_key(|stage| stage.hit_points)
+    }
+}
+
+pub fn set_durability(state: &mut ObjectState, hit_points: i32, hardness: i32) {
+    state.set_int(HIT_POINTS_STATE, hit_points);
+    state.set_int(HARDNESS_STATE, hardness);
+}
+
+/// Hit points and hardness, nothing for tiles that can't be damaged
+pub fn durability(state: &ObjectState) -> Option<(i32, i32)> {
+    let hit_points = state.get_int(HIT_POINTS_STATE).ok()?;
+    let hardness = state.get_int(HARDNESS_STATE).unwrap_or(0);
+
+    Some((hit_points, hardness))
+}
+
+/// Damage left after hardness, hits softer than the tile do nothing
+pub fn damage_after_hardness(amount: f32, hardness: i32) -> i32 {
+    (amount - hardness as f32).max(0.) as i32
+}
@@ -0,0 +1,389 @@
+/// Module for data driven interactions
+///
+/// An entity can carry a list of handlers next to (or instead of) its
+/// interaction key. Handlers run from the highest priority down, each one
+/// whose conditions all hold applies its effects. A handler marked `stop`
+/// ends the chain, including the interaction registered under the key.
+use crate::{InventoryPolicy, TileComponents, TileInteraction, TileInteractionResult};
+use lab_core::prelude::*;
+use serde::{Deserialize, Serialize};
+
+pub type TileInteractionContext<'a> =
+    InteractionContext<'a, TileInteraction, TileComponents, Vec<TileInteractionResult>>;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    /// The entity interacting is of this type
+    SourceIs(InteractableType),
+    /// The entity interacting carries an item with this name
+    SourceHasItem(String),
+    /// The state of the tile has this value for the key
+    StateIs(String, StateValue),
+    /// The tile was bumped into or used
+    InteractionIs(InteractionType),
+    Not(Box<Condition>),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    /// Run the interaction registered under the key
+    Run(String),
+    /// Stop the source from moving into the tile
+    Block,
+    Message(String),
+    /// Show the sprite of the named tile in the palette
+    ChangeSprite(String),
+    SetState(String, StateValue),
+    /// Give the source a new item from the ItemCatalog
+    GiveItem(String),
+    Despawn(InventoryPolicy),
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InteractionHandler {
+    /// Handlers with a higher priority run first
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// Don't run any handlers after this one when it matched
+    #[serde(default)]
+    pub stop: bool,
+}
+
+/// Component holding the handlers of an entity
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct InteractionHandlers(pub Vec<InteractionHandler>);
+
+impl InteractionHandler {
+    pub fn new(priority: i32) -> InteractionHandler {
+        InteractionHandler {
+            priority,
+            ..Default::default()
+        }
+    }
+
+    pub fn when(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn then(mut self, effect: Effect) -> Self {
+        self.effects.push(effect);
+        self
+    }
+
+    pub fn and_stop(mut self) -> Self {
+        self.stop = true;
+        self
+    }
+
+    pub fn matches(&self, ctx: &TileInteractionContext) -> bool {
+        self.conditions.iter().all(|c| c.holds(ctx))
+    }
+}
+
+impl InteractionHandlers {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Run the matching handlers, the flag tells if the chain was stopped
+    pub fn run(&self, ctx: &TileInteractionContext) -> (Vec<TileInteractionResult>, bool) {
+        let mut handlers: Vec<&InteractionHandler> = self.0.iter().collect();
+        let mut results = Vec::new();
+        // effects on the state build on each other
+        let mut state = ctx
+            .interaction_query
+            .get::<ObjectState>(ctx.destination)
+            .map(|s| (*s).clone())
+            .unwrap_or_default();
+
+        // stable, so handlers with the same priority run in the order they were added
+        handlers.sort_by(|a, b| b.priority.cmp(&a.priority));
+
+        for handler in handlers {
+            if !handler.matches(ctx) {
+                continue;
+            }
+
+            for effect in handler.effects.iter() {
+                results.extend(effect.apply(ctx, &mut state));
+            }
+
+            if handler.stop {
+                return (results, true);
+            }
+        }
+
+        (results, false)
+    }
+}
+
+impl Condition {
+    pub fn holds(&self, ctx: &TileInteractionContext) -> bool {
+        match self {
+            Condition::SourceIs(interactable_type) => ctx
+                .interaction_query
+                .get::<InteractableType>(ctx.source)
+                .map(|t| *t == *interactable_type)
+                .unwrap_or(false),
+            Condition::SourceHasItem(name) => {
+                let inventory = match ctx.interaction_query.get::<Inventory>(ctx.source) {
+                    Ok(inventory) => inventory,
+                    Err(_) => return false,
+                };
+
+                inventory.0.iter().any(|handle| {
+                    ctx.items
+                        .items
+                        .get(handle)
+                        .and_then(|entity| ctx.item_query.get::<Named>(*entity).ok())
+                        .map(|item| item.0 == *name)
+                        .unwrap_or(false)
+                })
+            }
+            Condition::StateIs(key, value) => ctx
+                .interaction_query
+                .get::<ObjectState>(ctx.destination)
+                .map(|state| state.get(key) == Ok(value))
+                .unwrap_or(false),
+            Condition::InteractionIs(interaction_type) => ctx.interaction_type == *interaction_type,
+            Condition::Not(condition) => !condition.holds(ctx),
+        }
+    }
+}
+
+impl Effect {
+    pub fn apply(
+        &self,
+        ctx: &TileInteractionContext,
+        state: &mut ObjectState,
+    ) -> Vec<TileInteractionResult> {
+        match self {
+            Effect::Run(key) => match ctx
+                .world_catalog
+                .get_interaction_by_key(&InteractionKey::new(key))
+            {
+                Some(interaction) => interaction.interact(InteractionContext {
+                    interaction_type: ctx.interaction_type.clone(),
+                    ..*ctx
+                }),
+                None => {
+                    log::warn!("No interaction registered as {}", key);
+                    Vec::new()
+                }
+            },
+            Effect::Block => TileInteractionResult::Block(ctx.source).into(),
+            Effect::Message(message) => TileInteractionResult::Message(message.clone()).into(),
+            Effect::ChangeSprite(tile_name) => match ctx.world_catalog.components.get(tile_name) {
+                Some(tile) => {
+                    TileInteractionResult::ChangeSprite(ctx.destination, tile.sprite.clone()).into()
+                }
+                None => {
+                    log::warn!("Cannot find tile {} in the palette", tile_name);
+                    Vec::new()
+                }
+            },
+            Effect::SetState(key, value) => {
+                state.set(key, value.clone());
+
+                TileInteractionResult::ChangeState(ctx.destination, state.clone()).into()
+            }
+            Effect::GiveItem(id) => TileInteractionResult::SpawnItem(ctx.source, id.clone()).into(),
+            Effect::Despawn(policy) => {
+                TileInteractionResult::Despawn(ctx.destination, *policy).into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keys, register_builtin_interactions, TilePalette};
+    use bevy::ecs::Schedule;
+    use lab_data::ItemDefinition;
+
+    /// The handlers to run and what came out of them
+    struct Probe {
+        source: Entity,
+        destination: Entity,
+        interaction_type: InteractionType,
+        handlers: InteractionHandlers,
+        results: Vec<TileInteractionResult>,
+        stopped: bool,
+    }
+
+    fn run_handlers(
+        items: ResMut<Items>,
+        settings: Res<WorldSettings>,
+        mut probe: ResMut<Probe>,
+        palette: Res<TilePalette>,
+        item_query: Query<(Entity, &ItemType, &Named, &ItemDefinition)>,
+        interactable_query: Query<(
+            Entity,
+            &InteractableType,
+            &ObjectState,
+            &InteractionKey,
+            &Inventory,
+        )>,
+    ) {
+        let ctx = InteractionContext {
+            source: probe.source,
+            destination: probe.destination,
+            interaction_type: probe.interaction_type.clone(),
+            items: &items,
+            settings: &settings,
+            world_catalog: &palette,
+            interaction_query: &interactable_query,
+            item_query: &item_query,
+        };
+
+        let (results, stopped) = probe.handlers.run(&ctx);
+
+        probe.results = results;
+        probe.stopped = stopped;
+    }
+
+    /// A player carrying a key bumps into a closed door
+    fn run(
+        handlers: Vec<InteractionHandler>,
+        interaction_type: InteractionType,
+    ) -> (Vec<TileInteractionResult>, bool) {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut items = Items::default();
+        let mut palette = TilePalette::default();
+
+        register_builtin_interactions(&mut palette);
+
+        let key = ItemComponents {
+            name: Named("Key".into()),
+            ..Default::default()
+        };
+        let handle = key.handle;
+        items.items.insert(handle, world.spawn(key));
+
+        let source = world.spawn((
+            InteractableType::Player,
+            ObjectState::default(),
+            InteractionKey::default(),
+            Inventory(vec![handle]),
+        ));
+
+        let mut state = ObjectState::default();
+        state.set_bool("open", false);
+
+        let destination = world.spawn((
+            InteractableType::Tile,
+            state,
+            InteractionKey::new(keys::BUMP),
+            Inventory::default(),
+        ));
+
+        resources.insert(items);
+        resources.insert(palette);
+        resources.insert(WorldSettings::default());
+        resources.insert(Probe {
+            source,
+            destination,
+            interaction_type,
+            handlers: InteractionHandlers(handlers),
+            results: Vec::new(),
+            stopped: false,
+        });
+
+        let mut schedule = Schedule::default();
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", run_handlers.system());
+        schedule.initialize(&mut world, &mut resources);
+        schedule.run(&mut world, &mut resources);
+
+        let probe = resources.get::<Probe>().unwrap();
+
+        (probe.results.clone(), probe.stopped)
+    }
+
+    fn message(text: &str) -> TileInteractionResult {
+        TileInteractionResult::Message(text.to_string())
+    }
+
+    fn holds(condition: Condition) -> bool {
+        let handler = InteractionHandler::new(0)
+            .when(condition)
+            .then(Effect::Message("held".into()));
+
+        !run(vec![handler], InteractionType::Collision).0.is_empty()
+    }
+
+    #[test]
+    fn handlers_run_by_priority_until_one_stops() {
+        let handlers = vec![
+            InteractionHandler::new(0).then(Effect::Message("last".into())),
+            InteractionHandler::new(5).then(Effect::Message("first".into())),
+            InteractionHandler::new(5)
+                .then(Effect::Message("second".into()))
+                .and_stop(),
+            // a handler that doesn't match doesn't stop anything
+            InteractionHandler::new(9)
+                .when(Condition::SourceIs(InteractableType::Npc))
+                .then(Effect::Message("npc".into()))
+                .and_stop(),
+        ];
+
+        let (results, stopped) = run(handlers, InteractionType::Collision);
+
+        assert_eq!(results, vec![message("first"), message("second")]);
+        assert!(stopped);
+
+        let (results, stopped) = run(
+            vec![
+                InteractionHandler::new(0).then(Effect::Run(keys::BUMP.into())),
+                InteractionHandler::new(1).then(Effect::Message("hello".into())),
+            ],
+            InteractionType::Collision,
+        );
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0], message("hello"));
+        assert!(matches!(results[1], TileInteractionResult::Block(_)));
+        assert!(!stopped);
+    }
+
+    #[test]
+    fn conditions_look_at_the_source_the_tile_and_the_interaction() {
+        assert!(holds(Condition::SourceIs(InteractableType::Player)));
+        assert!(!holds(Condition::SourceIs(InteractableType::Npc)));
+
+        assert!(holds(Condition::SourceHasItem("Key".into())));
+        assert!(!holds(Condition::SourceHasItem("Gold".into())));
+
+        assert!(holds(Condition::StateIs(
+            "open".into(),
+            StateValue::Bool(false)
+        )));
+        assert!(!holds(Condition::StateIs(
+            "open".into(),
+            StateValue::Bool(true)
+        )));
+        assert!(!holds(Condition::StateIs(
+            "locked".into(),
+            StateValue::Bool(false)
+        )));
+
+        assert!(holds(Condition::InteractionIs(InteractionType::Collision)));
+        assert!(!holds(Condition::InteractionIs(InteractionType::Action(
+            "use".into()
+        ))));
+
+        assert!(holds(Condition::Not(Box::new(Condition::SourceIs(
+            InteractableType::Npc
+        )))));
+        assert!(!holds(Condition::Not(Box::new(Condition::SourceIs(
+            InteractableType::Player
+        )))));
+    }
+}
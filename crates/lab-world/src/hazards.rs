@@ -0,0 +1,377 @@
+/// Module for traps and other dangers of the labyrinth
+///
+/// A Hazard goes off when something steps on it, on a timer or when a signal
+/// turns on. What it does is sent as TileInteractionResults for whoever is
+/// standing on it. Hidden hazards aren't drawn until a player notices them,
+/// which is a wisdom check when walking up to one, or until they go off.
+use crate::{
+    InventoryPolicy, SignalEvent, Status, StatusKind, TileInteractionResult,
+    TileInteractionResultEvent,
+};
+use lab_core::prelude::*;
+use lab_entities::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HazardKind {
+    Spikes,
+    /// Slides whoever steps in it a tile further
+    SpilledBeer,
+    /// Fermentation gas, poisons whoever breathes it
+    GasCloud,
+    /// Gives way and drops whoever is on it a level down
+    CollapsingFloor,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HazardTrigger {
+    /// Goes off for whatever steps on it
+    Step,
+    /// Goes off every so many seconds for whatever is on it
+    Timer(f32),
+    /// Goes off for whatever is on it when the channel turns on
+    Signal(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hazard {
+    pub kind: HazardKind,
+    pub trigger: HazardTrigger,
+    #[serde(default)]
+    pub hidden: bool,
+    /// What a perception roll has to reach to notice it
+    #[serde(default)]
+    pub difficulty: u32,
+    #[serde(default)]
+    pub damage: f32,
+}
+
+impl Hazard {
+    pub fn new(kind: HazardKind, trigger: HazardTrigger) -> Hazard {
+        Hazard {
+            kind,
+            trigger,
+            hidden: false,
+            difficulty: 10,
+            damage: 10.,
+        }
+    }
+
+    pub fn hidden(mut self, difficulty: u32) -> Self {
+        self.hidden = true;
+        self.difficulty = difficulty;
+        self
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self.kind {
+            HazardKind::Spikes => "spikes in the floor",
+            HazardKind::SpilledBeer => "a slick of spilled beer",
+            HazardKind::GasCloud => "a cloud of fermentation gas",
+            HazardKind::CollapsingFloor => "a crack in the floor",
+        }
+    }
+
+    /// What happens to the victim, `slide` is how far it slips in spilled beer
+    pub fn effects(
+        &self,
+        hazard: Entity,
+        victim: Entity,
+        slide: Option<Vec3>,
+    ) -> Vec<TileInteractionResult> {
+        match self.kind {
+            HazardKind::Spikes => vec![
+                TileInteractionResult::Damage(hazard, victim, self.damage),
+                TileInteractionResult::Message("Spikes shoot up from the floor!".into()),
+            ],
+            HazardKind::SpilledBeer => {
+                let mut results = vec![
+                    TileInteractionResult::Status(victim, Status::new(StatusKind::Slipping, 1.)),
+                    TileInteractionResult::Message("You slip in a puddle of beer!".into()),
+                ];
+
+                if let Some(to) = slide {
+                    results.push(TileInteractionResult::Move(
+                        victim,
+                        Location(to.x(), to.y(), to.z(), WorldLocation::World),
+                    ));
+                }
+
+                results
+            }
+            HazardKind::GasCloud => vec![
+                TileInteractionResult::Status(victim, Status::new(StatusKind::Poisoned, 5.)),
+                TileInteractionResult::Message("The fermentation gas burns your lungs!".into()),
+            ],
+            HazardKind::CollapsingFloor => vec![
+                TileInteractionResult::Message("The floor gives way beneath you!".into()),
+                TileInteractionResult::ChangeLevel(victim, 1),
+                TileInteractionResult::Despawn(hazard, InventoryPolicy::Destroy),
+            ],
+        }
+    }
+}
+
+/// A d20 plus the wisdom of the player against the difficulty
+pub fn perceives(stats: &Stats, difficulty: u32) -> bool {
+    rand::thread_rng().gen_range(1, 21) + stats.wisdom() >= difficulty
+}
+
+#[derive(Default)]
+pub struct HazardState {
+    pub signal_events: EventReader<SignalEvent>,
+    /// What stood on each hazard last frame
+    pub standing: HashMap<Entity, Vec<Entity>>,
+    /// Players next to each hazard last frame, they only get one look
+    pub nearby: HashMap<Entity, Vec<Entity>>,
+    pub timers: HashMap<Entity, Timer>,
+    pub despawned_events: EventReader<EntityDespawnedEvent>,
+}
+
+impl HazardState {
+    /// Drop what is known about a hazard that is gone, its entity can come back
+    /// as a new one
+    pub fn forget(&mut self, hazard: Entity) {
+        self.standing.remove(&hazard);
+        self.nearby.remove(&hazard);
+        self.timers.remove(&hazard);
+    }
+}
+
+/// Hazard System
+/// Sets hazards off, lets players notice the hidden ones and only draws the
+/// ones that were found.
+pub fn hazard_system(
+    time: Res<Time>,
+    mut state: Local<HazardState>,
+    index: Res<SpatialIndex>,
+    settings: Res<WorldSettings>,
+    signals: Res<Events<SignalEvent>>,
+    despawned_events: Res<Events<EntityDespawnedEvent>>,
+    mut results: ResMut<Events<TileInteractionResultEvent>>,
+    mut hazard_query: Query<(Entity, &mut Hazard, &mut Draw, &Transform)>,
+    victim_query: Query<(&Health, &Movement, &Transform)>,
+    player_query: Query<(&Player, &Stats)>,
+) {
+    let gone: Vec<Entity> = state
+        .despawned_events
+        .iter(&despawned_events)
+        .map(|event| event.entity)
+        .chain(hazard_query.removed::<Hazard>().iter().cloned())
+        .collect();
+
+    for hazard in gone {
+        state.forget(hazard);
+    }
+
+    let switched_on: Vec<String> = state
+        .signal_events
+        .iter(&signals)
+        .filter(|signal| signal.active)
+        .map(|signal| signal.channel.clone())
+        .collect();
+
+    for (hazard_entity, mut hazard, mut draw, transform) in &mut hazard_query.iter() {
+        let bounds = match index.get(hazard_entity) {
+            Some(bounds) => *bounds,
+            None => continue,
+        };
+        let tile = settings.tile(transform.scale().x());
+        let spot = TileCoord::from_world(bounds.center(), tile);
+
+        // only who is on top, not what is next to it or a crate pushed onto it
+        let standing: Vec<Entity> = index
+            .query_aabb(&Bounds::from_center(bounds.center(), bounds.size() / 2.))
+            .into_iter()
+            .filter(|e| *e != hazard_entity && victim_query.get::<Health>(*e).is_ok())
+            .collect();
+
+        let before = state
+            .standing
+            .insert(hazard_entity, standing.clone())
+            .unwrap_or_default();
+
+        let victims: Vec<Entity> = match &hazard.trigger {
+            HazardTrigger::Step => standing
+                .iter()
+                .filter(|e| !before.contains(*e))
+                .cloned()
+                .collect(),
+            HazardTrigger::Timer(seconds) => {
+                let timer = state
+                    .timers
+                    .entry(hazard_entity)
+                    .or_insert_with(|| Timer::from_seconds(*seconds, true));
+
+                timer.tick(time.delta_seconds);
+
+                if timer.finished {
+                    standing.clone()
+                } else {
+                    Vec::new()
+                }
+            }
+            HazardTrigger::Signal(channel) if switched_on.contains(channel) => standing.clone(),
+            HazardTrigger::Signal(_) => Vec::new(),
+        };
+
+        for victim in victims.iter() {
+            // spilled beer keeps you going the way you were going
+            let slide = victim_query
+                .get::<Movement>(*victim)
+                .ok()
+                .map(|movement| CardinalDirection::from_vec2(movement.direction.truncate()))
+                .filter(|direction| *direction != CardinalDirection::None)
+                .and_then(|direction| {
+                    let transform = victim_query.get::<Transform>(*victim).ok()?;
+                    let tile = settings.tile(transform.scale().x());
+
+                    Some(
+                        TileCoord::from_world(transform.translation().truncate(), tile)
+                            .neighbor(direction)
+                            .to_world(tile)
+                            .extend(transform.translation().z()),
+                    )
+                });
+
+            for result in hazard.effects(hazard_entity, *victim, slide) {
+                results.send(TileInteractionResultEvent::new(
+                    hazard_entity,
+                    *victim,
+                    result,
+                ));
+            }
+        }
+
+        // going off gives it away
+        if !victims.is_empty() {
+            hazard.hidden = false;
+        }
+
+        if hazard.hidden {
+            // the hazard's tile and the eight around it
+            let around = Bounds::from_center(spot.to_world(tile), tile * 3.);
+
+            let nearby: Vec<Entity> = index
+                .query_aabb(&around)
+                .into_iter()
+                .filter(|e| player_query.get::<Player>(*e).is_ok())
+                .filter(|e| {
+                    index
+                        .get(*e)
+                        .map(|player| {
+                            TileCoord::from_world(player.center(), tile).distance(&spot) <= 1
+                        })
+                        .unwrap_or(false)
+                })
+                .collect();
+
+            let before = state
+                .nearby
+                .insert(hazard_entity, nearby.clone())
+                .unwrap_or_default();
+
+            let noticed = nearby
+                .iter()
+                .filter(|e| !before.contains(*e))
+                .any(|player| {
+                    player_query
+                        .get::<Stats>(*player)
+                        .map(|stats| perceives(&stats, hazard.difficulty))
+                        .unwrap_or(false)
+                });
+
+            if noticed {
+                hazard.hidden = false;
+
+                results.send(TileInteractionResultEvent::new(
+                    hazard_entity,
+                    hazard_entity,
+                    TileInteractionResult::Message(format!("You notice {}.", hazard.description())),
+                ));
+            }
+        }
+
+        if draw.is_visible == hazard.hidden {
+            draw.is_visible = !hazard.hidden;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::Schedule;
+
+    #[test]
+    fn only_the_living_are_hurt_by_hazards() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+
+        resources.insert(Time::default());
+        resources.insert(SpatialIndex::default());
+        resources.insert(WorldSettings::default());
+        resources.insert(Events::<SignalEvent>::default());
+        resources.insert(Events::<EntityDespawnedEvent>::default());
+        resources.insert(Events::<TileInteractionResultEvent>::default());
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", hazard_system.system());
+        schedule.initialize(&mut world, &mut resources);
+
+        let at = Transform::from_translation(Vec3::zero());
+        let spikes = Hazard::new(HazardKind::Spikes, HazardTrigger::Step);
+        let hazard = world.spawn((spikes, Draw::default(), at));
+        let player = world.spawn((
+            Player::default(),
+            Stats::new(),
+            Health::default(),
+            Movement::default(),
+            at,
+        ));
+        let pushed = world.spawn((Moveable, Movement::default(), at));
+
+        for entity in [hazard, player, pushed].iter() {
+            resources.get_mut::<SpatialIndex>().unwrap().insert(
+                *entity,
+                Bounds::from_center(Vec2::zero(), Vec2::new(16., 16.)),
+            );
+        }
+
+        schedule.run(&mut world, &mut resources);
+
+        let events = resources
+            .get::<Events<TileInteractionResultEvent>>()
+            .unwrap();
+        let hurt: Vec<Entity> = events
+            .get_reader()
+            .iter(&events)
+            .filter_map(|event| match event.result {
+                TileInteractionResult::Damage(_, victim, _) => Some(victim),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(hurt, vec![player]);
+    }
+
+    #[test]
+    fn hazards_that_are_gone_are_forgotten() {
+        let mut world = World::new();
+        let hazard = world.spawn((Hazard::new(HazardKind::GasCloud, HazardTrigger::Timer(1.)),));
+        let player = world.spawn((Player::default(),));
+        let mut state = HazardState::default();
+
+        state.standing.insert(hazard, vec![player]);
+        state.nearby.insert(hazard, vec![player]);
+        state.timers.insert(hazard, Timer::from_seconds(1., true));
+
+        state.forget(hazard);
+
+        assert!(state.standing.is_empty());
+        assert!(state.nearby.is_empty());
+        assert!(state.timers.is_empty());
+    }
+}
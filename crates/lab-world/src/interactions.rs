@@ -0,0 +1,188 @@
+/// Module for the interactions every world has
+///
+/// Maps, blueprints and saves refer to these by key, games can register
+/// their own interactions next to them in the TilePalette.
+use crate::{
+    settings, InventoryPolicy, TileComponents, TileInteraction, TileInteractionResult, TilePalette,
+    KEY_ID_ATTRIBUTE,
+};
+use lab_core::prelude::*;
+use lab_data::ItemDefinition;
+
+pub mod keys {
+    /// Stops whatever walked into the tile
+    pub const BUMP: &'static str = "bump";
+    /// Does nothing
+    pub const NONE: &'static str = "none";
+    /// Moves the item on the tile into the inventory of the player
+    pub const ITEM_PICKUP: &'static str = "item.pickup";
+    /// Stops whoever has no key for the Lock of the tile, unlocks it for the rest
+    pub const LOCK_UNLOCK: &'static str = "lock.unlock";
+    /// Slides a Moveable object one tile away from the player walking into it
+    pub const OBJECT_PUSH: &'static str = "object.push";
+    /// Flips levers and presses buttons, see SignalWiring
+    pub const SIGNAL_SWITCH: &'static str = "signal.switch";
+    /// Blocks until the signals the tile listens to turn it on
+    pub const SIGNAL_GATE: &'static str = "signal.gate";
+}
+
+pub fn register_builtin_interactions(palette: &mut TilePalette) {
+    palette.register_interaction(
+        keys::NONE,
+        TileInteraction {
+            caller: |_| TileInteractionResult::None.into(),
+            description: "Null",
+        },
+    );
+
+    palette.register_interaction(
+        keys::BUMP,
+        TileInteraction {
+            caller: |ctx| TileInteractionResult::Block(ctx.source).into(),
+            description: "Bump",
+        },
+    );
+
+    palette.register_interaction(
+        keys::ITEM_PICKUP,
+        TileInteraction {
+            caller: pick_up_item,
+            description: "Get Item",
+        },
+    );
+
+    palette.register_interaction(
+        keys::LOCK_UNLOCK,
+        TileInteraction {
+            caller: crate::locks::unlock,
+            description: "Unlock",
+        },
+    );
+
+    palette.register_interaction(
+        keys::OBJECT_PUSH,
+        TileInteraction {
+            caller: push_object,
+            description: "Push",
+        },
+    );
+
+    palette.register_interaction(
+        keys::SIGNAL_SWITCH,
+        TileInteraction {
+            caller: crate::signals::switch,
+            description: "Switch",
+        },
+    );
+
+    palette.register_interaction(
+        keys::SIGNAL_GATE,
+        TileInteraction {
+            caller: crate::signals::gate,
+            description: "Gate",
+        },
+    );
+}
+
+/// Sprite an item is drawn with while it lies in the world
+pub fn item_sprite(definition: &ItemDefinition) -> &str {
+    if definition.sprite.is_empty() {
+        settings::DROPPED_ITEM_SPRITE
+    } else {
+        &definition.sprite
+    }
+}
+
+fn pick_up_item(
+    ctx: InteractionContext<TileInteraction, TileComponents, Vec<TileInteractionResult>>,
+) -> Vec<TileInteractionResult> {
+    let query = ctx.interaction_query;
+
+    match query.get::<InteractableType>(ctx.source) {
+        Ok(source_type) if *source_type == InteractableType::Player => {}
+        _ => return TileInteractionResult::None.into(),
+    }
+
+    let definition = match query.get::<ItemDefinition>(ctx.destination) {
+        Ok(definition) => (*definition).clone(),
+        Err(_) => return TileInteractionResult::None.into(),
+    };
+    let name = definition.name.clone();
+
+    // items dropped from an inventory keep what they were, others are what
+    // their definition says
+    let mut item = ItemComponents::from(&definition);
+
+    // anything that names a lock is a key
+    if item.item_type == ItemType::default() && definition.attribute(KEY_ID_ATTRIBUTE).is_some() {
+        item.item_type = ItemType::Key;
+    }
+    if let Ok(weight) = query.get::<Weight>(ctx.destination) {
+        item.weight = *weight;
+    }
+    if let Ok(item_type) = query.get::<ItemType>(ctx.destination) {
+        item.item_type = (*item_type).clone();
+    }
+    if let Ok(item_slot) = query.get::<ItemSlot>(ctx.destination) {
+        item.item_slot = (*item_slot).clone();
+    }
+    if let Ok(handle) = query.get::<WorldHandle<Item>>(ctx.destination) {
+        item.handle = *handle;
+    }
+
+    vec![
+        TileInteractionResult::AddItem(ctx.source, item),
+        TileInteractionResult::Despawn(ctx.destination, InventoryPolicy::Destroy),
+        TileInteractionResult::Message(format!("You picked up the {}", name)),
+    ]
+}
+
+/// Moves the object a tile in the direction the player is walking. Whatever
+/// the object lands on gets a collision from it, anything that would block
+/// the player pushes it back.
+fn push_object(
+    ctx: InteractionContext<TileInteraction, TileComponents, Vec<TileInteractionResult>>,
+) -> Vec<TileInteractionResult> {
+    let query = ctx.interaction_query;
+
+    // pushed objects don't push each other
+    if query.get::<Moveable>(ctx.source).is_ok() {
+        return TileInteractionResult::Block(ctx.source).into();
+    }
+
+    match query.get::<InteractableType>(ctx.source) {
+        Ok(source_type) if *source_type == InteractableType::Player => {}
+        _ => return TileInteractionResult::Block(ctx.source).into(),
+    }
+
+    let direction = query
+        .get::<Movement>(ctx.source)
+        .map(|movement| CardinalDirection::from_vec2(movement.direction.truncate()))
+        .unwrap_or(CardinalDirection::None);
+
+    let transform = match (
+        query.get::<Moveable>(ctx.destination),
+        query.get::<Transform>(ctx.destination),
+    ) {
+        (Ok(_), Ok(transform)) if direction != CardinalDirection::None => transform,
+        _ => return TileInteractionResult::Block(ctx.source).into(),
+    };
+
+    let tile = ctx.settings.tile(transform.scale().x());
+    let target = TileCoord::from_world(transform.translation().truncate(), tile)
+        .neighbor(direction)
+        .to_world(tile);
+
+    vec![
+        TileInteractionResult::Block(ctx.source),
+        TileInteractionResult::Move(
+            ctx.destination,
+            Location(
+                target.x(),
+                target.y(),
+                transform.translation().z(),
+                WorldLocation::World,
+            ),
+        ),
+    ]
+}
@@ -1,13 +1,41 @@
 use lab_core::prelude::*;
 use lab_sprites::SpriteInfo;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
+mod concealment;
+mod destructible;
+mod handlers;
+mod hazards;
+mod interactions;
+mod locks;
+mod migrations;
+mod save;
+mod signals;
+mod statuses;
+mod slots;
 mod systems;
 
+pub use concealment::*;
+pub use destructible::*;
+pub use handlers::*;
+pub use hazards::*;
+pub use interactions::*;
+pub use locks::*;
+pub use migrations::*;
+pub use save::*;
+pub use signals::*;
+pub use statuses::*;
+pub use slots::*;
+
 pub mod settings {
     pub const TILE_SIZE: f32 = 16.;
     pub const WORLD_TILE_SIZE: f32 = 16.;
     pub const PLAYER_SPEED: f32 = 48.;
+    /// Taken off the sides of a mover's collider so it fits through doorways
+    pub const MOVER_INSET: f32 = 4.;
+    /// Sprite of items that were dropped into the world
+    pub const DROPPED_ITEM_SPRITE: &'static str = "item_50";
 }
 
 /// Plugin that will setup all of the rules of the world.
@@ -15,21 +43,44 @@ pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut AppBuilder) {
-        app.add_resource(InteractionCatalog::<
-            TileInteraction,
-            TileComponents,
-            Vec<TileInteractionResult>,
-        >::default())
+        let mut palette = TilePalette::default();
+
+        register_builtin_interactions(&mut palette);
+
+        app.add_resource(palette)
             .add_resource(UiTextState::default())
             .add_resource(InteractionState::default())
+            .init_resource::<SaveSlots>()
+            .init_resource::<SaveState>()
+            .init_resource::<SaveMigrations>()
+            .init_resource::<PlayTime>()
+            .add_event::<SaveWorldEvent>()
             .add_event::<TextChangeEvent>()
             .add_event::<InteractionEvent>()
             .add_event::<TileInteractionResultEvent>()
+            .add_event::<EntityDespawnedEvent>()
+            .add_event::<SignalEvent>()
+            .add_event::<LevelChangeEvent>()
             //.add_system(systems::add_world_sprites_system.system())
             //.add_system(systems::add_interaction_sprites_system.system())
             .add_system_to_stage(lab_core::stages::PRE_UPDATE, systems::zoom_system.system())
+            .add_system(systems::save_keyboard_system.system())
+            .add_system(systems::play_time_system.system())
             .add_system(systems::save_world_system.thread_local_system())
+            .add_system(systems::collider_system.system())
             .add_system(systems::collision_system.system())
+            .add_system(systems::grid_movement_system.system())
+            .add_system(systems::player_action_system.system())
+            .add_system(systems::turn_system.system())
+            .add_system(signals::signal_plate_system.system())
+            .add_system(signals::signal_system.system())
+            .add_system(hazards::hazard_system.system())
+            .add_system(statuses::status_system.system())
+            .add_system(concealment::concealment_system.system())
+            .add_system(systems::mob_loot_system.system())
+            .add_system(systems::level_change_system.system())
+            .add_system_to_stage(stage::LAST, systems::state_change_system.system())
+            .add_system_to_stage(stage::LAST, systems::spatial_index_system.system())
             .add_system(systems::sprite_despawn_system.system())
             .add_system_to_stage(
                 lab_core::stages::POST_UPDATE,
@@ -61,20 +112,53 @@ impl CatalogItem for TileComponents {
     }
 }
 
+/// Sent for every result of an interaction, systems can send their own
 pub struct TileInteractionResultEvent {
-    _source: Entity,
-    destination: Entity,
-    result: TileInteractionResult,
+    pub source: Entity,
+    pub destination: Entity,
+    pub result: TileInteractionResult,
+}
+
+impl TileInteractionResultEvent {
+    pub fn new(
+        source: Entity,
+        destination: Entity,
+        result: TileInteractionResult,
+    ) -> TileInteractionResultEvent {
+        TileInteractionResultEvent {
+            source,
+            destination,
+            result,
+        }
+    }
+}
+/// What happens to the inventory of a despawned entity
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum InventoryPolicy {
+    /// The items are gone with the entity
+    Destroy,
+    /// The items are left behind where the entity was
+    Drop,
 }
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum TileInteractionResult {
     Damage(Entity, Entity, f32),
     ChangeSprite(Entity, SpriteInfo),
     ChangeInventory(Entity, Inventory),
     AddItem(Entity, ItemComponents),
+    /// Put a new item from the ItemCatalog, by id, in the inventory of the entity
+    SpawnItem(Entity, String),
+    /// Take the item out of the inventory of the entity and destroy it
+    RemoveItem(Entity, WorldHandle<Item>),
     ChangeState(Entity, ObjectState),
     Move(Entity, Location),
-    Despawn,
+    /// Remove the entity and its children from the world
+    Despawn(Entity, InventoryPolicy),
+    /// Put the entity under a status, see Statuses
+    Status(Entity, Status),
+    /// Send the entity down a number of levels, up when negative
+    ChangeLevel(Entity, i32),
     Log(String),
     Message(String),
     Menu(MenuDefinition),
@@ -135,7 +219,12 @@ pub struct TileComponents {
     pub sprite: SpriteInfo,
     pub state: ObjectState,
     pub zoomable: Zoomable,
-    pub interaction: WorldHandle<TileInteraction>,
+    pub interaction: InteractionKey,
+    pub handlers: InteractionHandlers,
+    pub lock: Lock,
+    pub destructible: Destructible,
+    pub wiring: SignalWiring,
+    pub concealment: Concealment,
 }
 impl Clone for TileComponents {
     fn clone(&self) -> Self {
@@ -146,6 +235,11 @@ impl Clone for TileComponents {
             state: self.state.clone(),
             zoomable: self.zoomable.clone(),
             interaction: self.interaction.clone(),
+            handlers: self.handlers.clone(),
+            lock: self.lock.clone(),
+            destructible: self.destructible.clone(),
+            wiring: self.wiring.clone(),
+            concealment: self.concealment.clone(),
         }
     }
 }
@@ -157,11 +251,24 @@ impl Default for TileComponents {
             sprite: SpriteInfo::default(),
             zoomable: Zoomable,
             state: ObjectState::default(),
-            interaction: WorldHandle::default(),
+            interaction: InteractionKey::default(),
+            handlers: InteractionHandlers::default(),
+            lock: Lock::default(),
+            destructible: Destructible::default(),
+            wiring: SignalWiring::default(),
+            concealment: Concealment::default(),
         }
     }
 }
 
+/// Sent when something has to go to another level of the labyrinth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LevelChangeEvent {
+    pub entity: Entity,
+    /// Levels down, negative is up
+    pub levels: i32,
+}
+
 pub struct TextChangeEvent {
     pub text: String,
     pub name: String,
@@ -171,6 +278,22 @@ pub struct UiTextState {
     pub change_events: EventReader<TextChangeEvent>,
 }
 #[derive(Default)]
+pub struct ActionState {
+    pub action_events: EventReader<lab_input::PlayerActionEvent>,
+}
+#[derive(Default)]
+pub struct TurnState {
+    pub action_events: EventReader<lab_input::PlayerActionEvent>,
+}
+#[derive(Default)]
+pub struct LevelChangeState {
+    pub level_changes: EventReader<LevelChangeEvent>,
+}
+#[derive(Default)]
+pub struct SpatialIndexState {
+    pub despawned_events: EventReader<EntityDespawnedEvent>,
+}
+#[derive(Default)]
 pub struct InteractionState {
     pub interaction_events: EventReader<InteractionEvent>,
     pub interaction_results: EventReader<TileInteractionResultEvent>,
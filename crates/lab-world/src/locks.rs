@@ -0,0 +1,127 @@
+/// Module for locks and the keys that open them
+///
+/// A Lock names the key it needs, a key is an `ItemType::Key` item with a
+/// `key_id` attribute holding the same name. Whether a lock is still locked
+/// is kept in the ObjectState of the tile.
+use crate::{TileInteractionContext, TileInteractionResult};
+use lab_core::prelude::*;
+use lab_data::ItemDefinition;
+use serde::{Deserialize, Serialize};
+
+/// Attribute of a key item naming the locks it opens
+pub const KEY_ID_ATTRIBUTE: &'static str = "key_id";
+/// ObjectState key recording whether a lock is locked
+pub const LOCKED_STATE: &'static str = "locked";
+
+/// A tile that needs a key, the default lock is no lock at all
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lock {
+    pub key_id: String,
+    /// The key is used up when the lock opens
+    #[serde(default)]
+    pub consume_key: bool,
+    /// Tile in the palette to show once the lock is open
+    #[serde(default)]
+    pub open_tile: Option<String>,
+}
+
+impl Lock {
+    pub fn new(key_id: &str) -> Lock {
+        Lock {
+            key_id: key_id.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.key_id.is_empty()
+    }
+}
+
+/// Locks stay locked until the state says otherwise
+pub fn is_locked(state: &ObjectState) -> bool {
+    state.get_bool(LOCKED_STATE).unwrap_or(true)
+}
+
+/// Find a key for the lock in the inventory of the entity
+pub fn find_key(
+    ctx: &TileInteractionContext,
+    holder: Entity,
+    lock: &Lock,
+) -> Option<WorldHandle<Item>> {
+    let inventory = ctx.interaction_query.get::<Inventory>(holder).ok()?;
+
+    inventory.0.iter().cloned().find(|handle| {
+        let entity = match ctx.items.items.get(handle) {
+            Some(entity) => *entity,
+            None => return false,
+        };
+
+        match (
+            ctx.item_query.get::<ItemType>(entity),
+            ctx.item_query.get::<ItemDefinition>(entity),
+        ) {
+            (Ok(item_type), Ok(definition)) => {
+                *item_type == ItemType::Key
+                    && definition
+                        .attribute(KEY_ID_ATTRIBUTE)
+                        .map(|key_id| key_id.value() == lock.key_id)
+                        .unwrap_or(false)
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Interaction for anything with a Lock, it stops whoever has no key and
+/// unlocks for whoever does.
+pub fn unlock(ctx: TileInteractionContext) -> Vec<TileInteractionResult> {
+    let query = ctx.interaction_query;
+
+    let lock = match query.get::<Lock>(ctx.destination) {
+        Ok(lock) if !lock.is_none() => (*lock).clone(),
+        _ => return TileInteractionResult::None.into(),
+    };
+    let mut state = match query.get::<ObjectState>(ctx.destination) {
+        Ok(state) => (*state).clone(),
+        Err(_) => return TileInteractionResult::None.into(),
+    };
+
+    if !is_locked(&state) {
+        return TileInteractionResult::None.into();
+    }
+
+    let key = match find_key(&ctx, ctx.source, &lock) {
+        Some(key) => key,
+        None => {
+            return vec![
+                TileInteractionResult::Block(ctx.source),
+                TileInteractionResult::Message("It's locked, maybe there's a key somewhere".into()),
+            ]
+        }
+    };
+
+    state.set_bool(LOCKED_STATE, false);
+
+    let mut results = vec![
+        TileInteractionResult::ChangeState(ctx.destination, state),
+        TileInteractionResult::Message("You have the key, it's unlocked!".into()),
+    ];
+
+    if lock.consume_key {
+        results.push(TileInteractionResult::RemoveItem(ctx.source, key));
+    }
+
+    if let Some(tile) = lock
+        .open_tile
+        .as_ref()
+        .and_then(|name| ctx.world_catalog.components.get(name))
+    {
+        results.push(TileInteractionResult::ChangeSprite(
+            ctx.destination,
+            tile.sprite.clone(),
+        ));
+    }
+
+    results
+}
@@ -0,0 +1,403 @@
+/// Module for upgrading saves written by older versions of the game
+///
+/// Records are migrated as json, before they are deserialized into the
+/// current component types. Every bump of SAVE_FORMAT_VERSION needs a
+/// migration registered from the previous version, e.g.
+///
+/// migrations.register(1, "Split wit into wit and cunning", |records| {
+///     for player in records.player.iter_mut() {
+///         default_field(player, &["stats", "cunning"], 0.into());
+///     }
+///     Ok(())
+/// });
+///
+/// Fields that are only added and have a serde default do not need a bump.
+use crate::keys;
+use crate::save::{SaveError, SAVE_FORMAT_VERSION};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// The raw records of a save
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveRecords {
+    pub player: Vec<Value>,
+    pub tiles: Vec<Value>,
+    pub items: Vec<Value>,
+}
+
+pub type MigrationFn = fn(&mut SaveRecords) -> Result<(), String>;
+
+#[derive(Clone)]
+pub struct Migration {
+    /// Version this migration upgrades from, it produces `from + 1`
+    pub from: u32,
+    pub description: &'static str,
+    pub migrate: MigrationFn,
+}
+
+/// Resource holding the upgrade path between save format versions
+#[derive(Clone)]
+pub struct SaveMigrations {
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl Default for SaveMigrations {
+    fn default() -> Self {
+        let mut migrations = SaveMigrations::empty();
+
+        migrations.register(1, "Reference tile interactions by key", |records| {
+            for tile in records.tiles.iter_mut() {
+                let key = v1_interaction_key(tile);
+                remove_field(tile, &["interaction"]);
+                default_field(tile, &["interaction"], key.into());
+            }
+            Ok(())
+        });
+
+        migrations.register(2, "Store object state as typed values", |records| {
+            for tile in records.tiles.iter_mut() {
+                upgrade_state_values(tile)?;
+            }
+            Ok(())
+        });
+
+        migrations
+    }
+}
+
+impl SaveMigrations {
+    pub fn empty() -> SaveMigrations {
+        SaveMigrations {
+            migrations: BTreeMap::new(),
+        }
+    }
+
+    /// Register the transform that upgrades records from `from` to `from + 1`
+    pub fn register(
+        &mut self,
+        from: u32,
+        description: &'static str,
+        migrate: MigrationFn,
+    ) -> &mut SaveMigrations {
+        if self.migrations.contains_key(&from) {
+            log::warn!("Replacing the save migration from version {}", from);
+        }
+
+        self.migrations.insert(
+            from,
+            Migration {
+                from,
+                description,
+                migrate,
+            },
+        );
+
+        self
+    }
+
+    /// Upgrade records written with `version` to the current save format
+    pub fn migrate(&self, records: &mut SaveRecords, version: u32) -> Result<(), SaveError> {
+        self.upgrade(records, version, SAVE_FORMAT_VERSION)
+    }
+
+    fn upgrade(&self, records: &mut SaveRecords, from: u32, to: u32) -> Result<(), SaveError> {
+        if from > to {
+            return Err(SaveError::FutureVersion(from));
+        }
+
+        for version in from..to {
+            let migration = self
+                .migrations
+                .get(&version)
+                .ok_or(SaveError::MissingMigration(version))?;
+
+            log::info!(
+                "Migrating save from version {}: {}",
+                version,
+                migration.description
+            );
+
+            (migration.migrate)(records)
+                .map_err(|message| SaveError::Migration(version, message))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Find the object holding the last field of `path`, creating objects on the way
+fn parent_mut<'a>(
+    record: &'a mut Value,
+    path: &[&str],
+) -> Option<&'a mut serde_json::Map<String, Value>> {
+    let (_last, parents) = path.split_last()?;
+    let mut current = record;
+
+    for key in parents {
+        current = current
+            .as_object_mut()?
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+
+    current.as_object_mut()
+}
+
+/// Set a field if the record doesn't have it yet
+pub fn default_field(record: &mut Value, path: &[&str], value: Value) {
+    if let (Some(parent), Some(last)) = (parent_mut(record, path), path.last()) {
+        parent.entry(last.to_string()).or_insert(value);
+    }
+}
+
+/// Move a field to a new name, next to the old one
+pub fn rename_field(record: &mut Value, path: &[&str], new_name: &str) {
+    if let (Some(parent), Some(last)) = (parent_mut(record, path), path.last()) {
+        if let Some(value) = parent.remove(*last) {
+            parent.insert(new_name.to_string(), value);
+        }
+    }
+}
+
+/// Drop a field that no longer exists
+pub fn remove_field(record: &mut Value, path: &[&str]) {
+    if let (Some(parent), Some(last)) = (parent_mut(record, path), path.last()) {
+        parent.remove(*last);
+    }
+}
+
+/// The builtin interaction of a version 1 tile. Version 1 stored random
+/// interaction handles, so the rest of the record has to tell which it was
+fn v1_interaction_key(tile: &Value) -> &'static str {
+    let interactable_type = tile.get("interactable_type").and_then(Value::as_str);
+    let has_item = !tile.get("item").unwrap_or(&Value::Null).is_null();
+
+    if has_item || interactable_type == Some("Item") {
+        keys::ITEM_PICKUP
+    } else if interactable_type.is_none() {
+        // nothing could interact with the tile
+        keys::NONE
+    } else {
+        log::warn!(
+            "Cannot tell the interaction of the saved tile {} at {}, it only bumps now",
+            tile.get("name").unwrap_or(&Value::Null),
+            tile.get("location").unwrap_or(&Value::Null),
+        );
+        keys::BUMP
+    }
+}
+
+/// Fields of a state value before version 3, only one of them was ever set
+const OLD_STATE_FIELDS: [&str; 3] = ["bool_value", "int_value", "string_value"];
+
+/// The value an old state value held, if the json is one
+fn old_state_value(value: &Value) -> Option<Option<Value>> {
+    let object = value.as_object()?;
+
+    if object.len() != OLD_STATE_FIELDS.len()
+        || !OLD_STATE_FIELDS
+            .iter()
+            .all(|field| object.contains_key(*field))
+    {
+        return None;
+    }
+
+    Some(
+        OLD_STATE_FIELDS
+            .iter()
+            .map(|field| &object[*field])
+            .find(|value| !value.is_null())
+            .cloned(),
+    )
+}
+
+/// Replace the old state values in the record, in the object state and in
+/// the conditions and effects of handlers alike
+fn upgrade_state_values(record: &mut Value) -> Result<(), String> {
+    if let Some(old) = old_state_value(record) {
+        *record = old.ok_or_else(|| "a state value holds no value".to_string())?;
+        return Ok(());
+    }
+
+    match record {
+        Value::Object(object) => {
+            // keys of an object state that never got a value held nothing
+            if let Some(Value::Object(values)) = object.get_mut("values") {
+                let empty: Vec<String> = values
+                    .iter()
+                    .filter(|(_, value)| old_state_value(value) == Some(None))
+                    .map(|(key, _)| key.clone())
+                    .collect();
+
+                for key in empty {
+                    values.remove(&key);
+                }
+            }
+
+            for value in object.values_mut() {
+                upgrade_state_values(value)?;
+            }
+        }
+        Value::Array(array) => {
+            for value in array.iter_mut() {
+                upgrade_state_values(value)?;
+            }
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use serde_json::{json, Value};
+
+    fn records() -> SaveRecords {
+        SaveRecords {
+            player: vec![json!({ "stats": { "wit": 3 } })],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn upgrades_in_order() {
+        let mut migrations = SaveMigrations::empty();
+
+        migrations
+            .register(2, "Rename wit", |records| {
+                for player in records.player.iter_mut() {
+                    rename_field(player, &["stats", "wit"], "cunning");
+                }
+                Ok(())
+            })
+            .register(1, "Add luck", |records| {
+                for player in records.player.iter_mut() {
+                    default_field(player, &["stats", "luck"], 7.into());
+                }
+                Ok(())
+            });
+
+        let mut records = records();
+
+        migrations.upgrade(&mut records, 1, 3).unwrap();
+
+        assert_eq!(
+            records.player[0],
+            json!({ "stats": { "cunning": 3, "luck": 7 } })
+        );
+    }
+
+    #[test]
+    fn tile_interactions_become_keys() {
+        let mut records = SaveRecords {
+            tiles: vec![
+                json!({ "name": "floor", "interaction": "a1b2", "interactable_type": null }),
+                json!({
+                    "name": "stout",
+                    "interaction": "c3d4",
+                    "interactable_type": "Item",
+                    "item": { "name": "Stout" },
+                }),
+            ],
+            ..Default::default()
+        };
+
+        SaveMigrations::default()
+            .upgrade(&mut records, 1, 2)
+            .unwrap();
+
+        assert_eq!(records.tiles[0]["interaction"], keys::NONE);
+        assert_eq!(records.tiles[1]["interaction"], keys::ITEM_PICKUP);
+    }
+
+    #[test]
+    fn tile_interactions_that_cannot_be_told_bump() {
+        let mut records = SaveRecords {
+            tiles: vec![
+                json!({ "name": "door", "interaction": "a1b2", "interactable_type": "Tile" }),
+            ],
+            ..Default::default()
+        };
+
+        SaveMigrations::default()
+            .upgrade(&mut records, 1, 2)
+            .unwrap();
+
+        assert_eq!(
+            records.tiles[0],
+            json!({ "name": "door", "interaction": keys::BUMP, "interactable_type": "Tile" })
+        );
+    }
+
+    #[test]
+    fn state_values_become_typed() {
+        let old = |bool_value: Value, int_value: Value, string_value: Value| {
+            json!({
+                "bool_value": bool_value,
+                "int_value": int_value,
+                "string_value": string_value,
+            })
+        };
+
+        let mut records = SaveRecords {
+            tiles: vec![json!({
+                "name": "door",
+                "state": { "values": {
+                    "open": old(true.into(), Value::Null, Value::Null),
+                    "hit_points": old(Value::Null, 12.into(), Value::Null),
+                    "owner": old(Value::Null, Value::Null, "brewer".into()),
+                    "nothing": old(Value::Null, Value::Null, Value::Null),
+                } },
+                "handlers": [{
+                    "conditions": [{ "Not": {
+                        "StateIs": ["open", old(false.into(), Value::Null, Value::Null)]
+                    } }],
+                    "effects": [{
+                        "SetState": ["hit_points", old(Value::Null, 3.into(), Value::Null)]
+                    }],
+                }],
+            })],
+            ..Default::default()
+        };
+
+        SaveMigrations::default()
+            .upgrade(&mut records, 2, 3)
+            .unwrap();
+
+        assert_eq!(
+            records.tiles[0],
+            json!({
+                "name": "door",
+                "state": { "values": { "open": true, "hit_points": 12, "owner": "brewer" } },
+                "handlers": [{
+                    "conditions": [{ "Not": { "StateIs": ["open", false] } }],
+                    "effects": [{ "SetState": ["hit_points", 3] }],
+                }],
+            })
+        );
+    }
+
+    #[test]
+    fn future_versions_are_rejected() {
+        let migrations = SaveMigrations::empty();
+
+        match migrations.upgrade(&mut records(), 4, 3) {
+            Err(SaveError::FutureVersion(4)) => {}
+            other => panic!("Expected a future version error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn gaps_are_reported() {
+        let mut migrations = SaveMigrations::empty();
+
+        migrations.register(1, "Nothing", |_| Ok(()));
+
+        match migrations.upgrade(&mut records(), 1, 3) {
+            Err(SaveError::MissingMigration(2)) => {}
+            other => panic!("Expected a missing migration error, got {:?}", other),
+        }
+    }
+}
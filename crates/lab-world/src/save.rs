@@ -0,0 +1,676 @@
+/// Module for persisting the world
+///
+/// A save is a sled database with one tree per record type. Every record is
+/// stored as json so a save can be read back without the game running.
+use crate::{
+    Concealment, Destructible, Hazard, InteractionHandlers, Lock, PlayTime, SaveMigrations,
+    SaveRecords, SaveSlots, SignalWiring, TileComponents,
+};
+use lab_core::prelude::*;
+use lab_data::ItemDefinition;
+use lab_entities::prelude::*;
+use lab_sprites::{SpriteInfo, SpriteLibrary};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Debug, Display};
+
+/// Version of the records written by this build of the game
+pub const SAVE_FORMAT_VERSION: u32 = 3;
+
+pub(crate) const META_TREE: &'static str = "meta";
+pub(crate) const PLAYER_TREE: &'static str = "player";
+pub(crate) const TILES_TREE: &'static str = "tiles";
+pub(crate) const ITEMS_TREE: &'static str = "items";
+const VERSION_KEY: &'static str = "version";
+const HANDLES_KEY: &'static str = "handles";
+
+/// Every tree that makes up a single save
+pub(crate) const SAVE_TREES: [&'static str; 4] = [META_TREE, PLAYER_TREE, TILES_TREE, ITEMS_TREE];
+
+/// Save or load the named slot
+#[derive(Clone, Debug, PartialEq)]
+pub enum SaveWorldEvent {
+    Save(String),
+    Load(String),
+}
+
+#[derive(Default)]
+pub struct SaveState {
+    pub save_events: EventReader<SaveWorldEvent>,
+}
+
+pub enum SaveError {
+    Database(sled::Error),
+    Format(serde_json::Error),
+    MissingVersion,
+    /// The save was written by a newer version of the game
+    FutureVersion(u32),
+    /// No migration is registered from this version
+    MissingMigration(u32),
+    /// A migration from this version failed
+    Migration(u32, String),
+    SlotNotFound(String),
+    SlotExists(String),
+}
+
+impl Debug for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::Database(err) => write!(f, "Save Error: database error {}", err),
+            SaveError::Format(err) => write!(f, "Save Error: bad record {}", err),
+            SaveError::MissingVersion => write!(f, "Save Error: save has no format version"),
+            SaveError::FutureVersion(version) => write!(
+                f,
+                "Save Error: save format {} was written by a newer game, this build reads up to {}",
+                version, SAVE_FORMAT_VERSION
+            ),
+            SaveError::MissingMigration(version) => write!(
+                f,
+                "Save Error: cannot upgrade save format {} to {}, no migration registered",
+                version,
+                version + 1
+            ),
+            SaveError::Migration(version, message) => write!(
+                f,
+                "Save Error: upgrading save format {} failed: {}",
+                version, message
+            ),
+            SaveError::SlotNotFound(slot) => {
+                write!(f, "Save Error: there is no slot named {}", slot)
+            }
+            SaveError::SlotExists(slot) => write!(f, "Save Error: slot {} already exists", slot),
+        }
+    }
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(self, f)
+    }
+}
+
+impl From<sled::Error> for SaveError {
+    fn from(err: sled::Error) -> Self {
+        SaveError::Database(err)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        SaveError::Format(err)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerRecord {
+    pub name: Named,
+    pub stats: Stats,
+    pub skills: Skills,
+    pub abilities: Abilities,
+    pub inventory: Inventory,
+    pub job: Job,
+    pub location: Location,
+    #[serde(default)]
+    pub health: Health,
+    #[serde(default)]
+    pub level: Level,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TileRecord {
+    pub name: Named,
+    pub location: Location,
+    pub scale: f32,
+    /// Name of the sprite in the SpriteLibrary, texture handles are not saved
+    pub sprite: String,
+    pub state: ObjectState,
+    pub interaction: InteractionKey,
+    #[serde(default)]
+    pub handlers: InteractionHandlers,
+    #[serde(default)]
+    pub lock: Lock,
+    #[serde(default)]
+    pub destructible: Destructible,
+    /// Pushed around by the player
+    #[serde(default)]
+    pub moveable: bool,
+    #[serde(default)]
+    pub wiring: SignalWiring,
+    #[serde(default)]
+    pub hazard: Option<Hazard>,
+    #[serde(default)]
+    pub concealment: Concealment,
+    pub interactable_type: Option<InteractableType>,
+    pub item: Option<ItemDefinition>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ItemRecord {
+    pub handle: WorldHandle<Item>,
+    pub name: Named,
+    pub weight: Weight,
+    pub item_type: ItemType,
+    pub item_slot: ItemSlot,
+    pub definition: ItemDefinition,
+}
+
+/// Everything needed to rebuild the world
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SaveGame {
+    pub version: u32,
+    pub player: Option<PlayerRecord>,
+    pub tiles: Vec<TileRecord>,
+    pub items: Vec<ItemRecord>,
+    /// Allocator state, so handles made after a load don't repeat saved ones
+    pub handles: HandleAllocator,
+}
+
+impl SaveGame {
+    /// Take a snapshot of the player, the placed tiles and the items registry
+    pub fn capture(world: &World, items: &Items) -> SaveGame {
+        let mut save = SaveGame {
+            version: SAVE_FORMAT_VERSION,
+            ..Default::default()
+        };
+
+        for (e, _player, name, stats, skills, abilities, inventory, job, location, transform) in
+            world
+                .query::<(
+                    Entity,
+                    &Player,
+                    &Named,
+                    &Stats,
+                    &Skills,
+                    &Abilities,
+                    &Inventory,
+                    &Job,
+                    &Location,
+                    &Transform,
+                )>()
+                .iter()
+        {
+            save.player = Some(PlayerRecord {
+                name: name.clone(),
+                stats: stats.clone(),
+                skills: skills.clone(),
+                abilities: abilities.clone(),
+                inventory: inventory.clone(),
+                job: job.clone(),
+                location: Location::new(*transform, location.3),
+                health: world
+                    .get::<Health>(e)
+                    .map(|health| *health)
+                    .unwrap_or_default(),
+                level: world
+                    .get::<Level>(e)
+                    .map(|level| *level)
+                    .unwrap_or_default(),
+            });
+        }
+
+        for (entity, name, location, sprite, state, interaction, transform) in world
+            .query::<(
+                Entity,
+                &Named,
+                &Location,
+                &SpriteInfo,
+                &ObjectState,
+                &InteractionKey,
+                &Transform,
+            )>()
+            .iter()
+        {
+            save.tiles.push(TileRecord {
+                name: name.clone(),
+                location: Location::new(*transform, location.3),
+                scale: transform.scale().x(),
+                sprite: sprite.name.clone(),
+                state: state.clone(),
+                interaction: interaction.clone(),
+                handlers: world
+                    .get::<InteractionHandlers>(entity)
+                    .map(|h| (*h).clone())
+                    .unwrap_or_default(),
+                lock: world
+                    .get::<Lock>(entity)
+                    .map(|l| (*l).clone())
+                    .unwrap_or_default(),
+                destructible: world
+                    .get::<Destructible>(entity)
+                    .map(|d| (*d).clone())
+                    .unwrap_or_default(),
+                moveable: world.get::<Moveable>(entity).is_ok(),
+                wiring: world
+                    .get::<SignalWiring>(entity)
+                    .map(|w| (*w).clone())
+                    .unwrap_or_default(),
+                hazard: world.get::<Hazard>(entity).ok().map(|h| (*h).clone()),
+                concealment: world
+                    .get::<Concealment>(entity)
+                    .map(|c| (*c).clone())
+                    .unwrap_or_default(),
+                interactable_type: world.get::<InteractableType>(entity).ok().map(|t| *t),
+                item: world
+                    .get::<ItemDefinition>(entity)
+                    .ok()
+                    .map(|d| (*d).clone()),
+            });
+        }
+
+        for (handle, entity) in items.items.iter() {
+            if let (Ok(name), Ok(weight), Ok(item_type), Ok(item_slot), Ok(definition)) = (
+                world.get::<Named>(*entity),
+                world.get::<Weight>(*entity),
+                world.get::<ItemType>(*entity),
+                world.get::<ItemSlot>(*entity),
+                world.get::<ItemDefinition>(*entity),
+            ) {
+                save.items.push(ItemRecord {
+                    handle: *handle,
+                    name: (*name).clone(),
+                    weight: *weight,
+                    item_type: (*item_type).clone(),
+                    item_slot: (*item_slot).clone(),
+                    definition: (*definition).clone(),
+                });
+            }
+        }
+
+        save
+    }
+
+    /// Replace the tiles and items in the world with the saved ones and
+    /// apply the saved player to the current player (or spawn one).
+    pub fn restore(self, world: &mut World, sprites: &SpriteLibrary, items: &mut Items) {
+        let stale: Vec<Entity> = world
+            .query::<(Entity, &SpriteInfo, &InteractionKey)>()
+            .iter()
+            .map(|(e, _, _)| e)
+            .chain(items.items.values().cloned())
+            .collect();
+
+        for entity in stale {
+            let _ = world.despawn(entity);
+        }
+
+        items.items.clear();
+
+        for record in self.items {
+            let handle = record.handle;
+
+            let entity = world.spawn(ItemComponents {
+                name: record.name,
+                weight: record.weight,
+                handle: handle,
+                item_type: record.item_type,
+                item_slot: record.item_slot,
+                description: record.definition,
+            });
+
+            items.items.insert(handle, entity);
+        }
+
+        for record in self.tiles {
+            let sprite = match sprites.get(&record.sprite) {
+                Some(sprite) => sprite.clone(),
+                None => {
+                    log::warn!("Cannot find sprite {} for saved tile", record.sprite);
+                    continue;
+                }
+            };
+
+            let entity = world.spawn(sprite.to_components(record.location.into(), record.scale));
+
+            // the interactable bundle comes with empty state, the record's goes on top
+            if let Some(interactable_type) = record.interactable_type {
+                world
+                    .insert(entity, Interactable::new(interactable_type))
+                    .expect("Tile entity was just spawned");
+            }
+
+            world
+                .insert(
+                    entity,
+                    TileComponents {
+                        name: record.name,
+                        location: record.location,
+                        sprite: sprite,
+                        state: record.state,
+                        interaction: record.interaction,
+                        handlers: record.handlers,
+                        lock: record.lock,
+                        destructible: record.destructible,
+                        wiring: record.wiring,
+                        concealment: record.concealment,
+                        ..Default::default()
+                    },
+                )
+                .expect("Tile entity was just spawned");
+
+            if record.moveable {
+                world
+                    .insert(entity, (Moveable, Movement::default()))
+                    .expect("Tile entity was just spawned");
+            }
+            if let Some(hazard) = record.hazard {
+                world
+                    .insert_one(entity, hazard)
+                    .expect("Tile entity was just spawned");
+            }
+            if let Some(definition) = record.item {
+                world
+                    .insert_one(entity, definition)
+                    .expect("Tile entity was just spawned");
+            }
+        }
+
+        if let Some(record) = self.player {
+            let existing = world
+                .query::<(Entity, &Player)>()
+                .iter()
+                .map(|(e, _)| e)
+                .last();
+
+            let entity = match existing {
+                Some(entity) => entity,
+                None => world.spawn(PlayerComponents::new(&record.name.0)),
+            };
+
+            if let Ok(mut transform) = world.get_mut::<Transform>(entity) {
+                transform.set_translation(record.location.into());
+            }
+
+            world
+                .insert(
+                    entity,
+                    (
+                        record.name,
+                        record.stats,
+                        record.skills,
+                        record.abilities,
+                        record.inventory,
+                        record.job,
+                        record.location,
+                        record.health,
+                        record.level,
+                    ),
+                )
+                .expect("Player entity exists");
+        }
+    }
+
+    /// Write the save into the trees belonging to `slot`
+    pub fn write(&self, db: &sled::Db, slot: &str) -> Result<(), SaveError> {
+        let meta = db.open_tree(tree_name(slot, META_TREE))?;
+
+        // a save without a version was never finished, an overwrite that is cut
+        // short must not pass for the save that was there before
+        meta.remove(VERSION_KEY)?;
+        meta.flush()?;
+
+        write_records(db, &tree_name(slot, PLAYER_TREE), self.player.iter())?;
+        write_records(db, &tree_name(slot, TILES_TREE), self.tiles.iter())?;
+        write_records(db, &tree_name(slot, ITEMS_TREE), self.items.iter())?;
+
+        meta.insert(HANDLES_KEY, serde_json::to_vec(&self.handles)?)?;
+        // the version goes in last
+        meta.insert(VERSION_KEY, serde_json::to_vec(&self.version)?)?;
+
+        db.flush()?;
+
+        Ok(())
+    }
+
+    /// Read the slot, upgrading older records with the registered migrations
+    pub fn read(
+        db: &sled::Db,
+        slot: &str,
+        migrations: &SaveMigrations,
+    ) -> Result<SaveGame, SaveError> {
+        let meta = db.open_tree(tree_name(slot, META_TREE))?;
+        let version: u32 = match meta.get(VERSION_KEY)? {
+            Some(value) => serde_json::from_slice(&value)?,
+            None => return Err(SaveError::MissingVersion),
+        };
+        let handles = match meta.get(HANDLES_KEY)? {
+            Some(value) => serde_json::from_slice(&value)?,
+            None => HandleAllocator::default(),
+        };
+
+        let mut records = SaveRecords {
+            player: read_records(db, &tree_name(slot, PLAYER_TREE))?,
+            tiles: read_records(db, &tree_name(slot, TILES_TREE))?,
+            items: read_records(db, &tree_name(slot, ITEMS_TREE))?,
+        };
+
+        migrations.migrate(&mut records, version)?;
+
+        Ok(SaveGame {
+            version: SAVE_FORMAT_VERSION,
+            player: from_values(records.player)?.pop(),
+            tiles: from_values(records.tiles)?,
+            items: from_values(records.items)?,
+            handles,
+        })
+    }
+}
+
+/// Slots share a database, each slot gets its own set of trees
+pub(crate) fn tree_name(slot: &str, tree: &str) -> String {
+    format!("{}/{}", slot, tree)
+}
+
+fn write_records<'a, T: Serialize + 'a>(
+    db: &sled::Db,
+    tree: &str,
+    records: impl Iterator<Item = &'a T>,
+) -> Result<(), SaveError> {
+    let tree = db.open_tree(tree)?;
+
+    tree.clear()?;
+
+    // big endian keys keep the records in the order they were written
+    for (i, record) in records.enumerate() {
+        tree.insert((i as u64).to_be_bytes(), serde_json::to_vec(record)?)?;
+    }
+
+    Ok(())
+}
+
+fn read_records(db: &sled::Db, tree: &str) -> Result<Vec<Value>, SaveError> {
+    let mut records = Vec::new();
+
+    for entry in db.open_tree(tree)?.iter() {
+        let (_key, value) = entry?;
+
+        records.push(serde_json::from_slice(&value)?);
+    }
+
+    Ok(records)
+}
+
+fn from_values<T: DeserializeOwned>(values: Vec<Value>) -> Result<Vec<T>, SaveError> {
+    values
+        .into_iter()
+        .map(|value| serde_json::from_value(value).map_err(SaveError::from))
+        .collect()
+}
+
+pub fn save_world(world: &World, resources: &Resources, slot: &str) -> Result<(), SaveError> {
+    let mut slots = resources
+        .get_mut::<SaveSlots>()
+        .expect("SaveSlots resource is missing");
+    let items = resources.get::<Items>().expect("Items resource is missing");
+    let play_time = resources
+        .get::<PlayTime>()
+        .expect("PlayTime resource is missing");
+
+    let mut save = SaveGame::capture(world, &items);
+
+    save.handles = resources
+        .get::<HandleAllocator>()
+        .expect("HandleAllocator resource is missing")
+        .clone();
+
+    slots.save(slot, &save, play_time.seconds)
+}
+
+pub fn load_world(world: &mut World, resources: &Resources, slot: &str) -> Result<(), SaveError> {
+    let mut slots = resources
+        .get_mut::<SaveSlots>()
+        .expect("SaveSlots resource is missing");
+    let migrations = resources
+        .get::<SaveMigrations>()
+        .expect("SaveMigrations resource is missing");
+    let (save, metadata) = slots.load(slot, &migrations)?;
+
+    let sprites = resources
+        .get::<SpriteLibrary>()
+        .expect("SpriteLibrary resource is missing");
+    let mut items = resources
+        .get_mut::<Items>()
+        .expect("Items resource is missing");
+
+    *resources
+        .get_mut::<HandleAllocator>()
+        .expect("HandleAllocator resource is missing") = save.handles.clone();
+
+    save.restore(world, &sprites, &mut items);
+
+    resources
+        .get_mut::<PlayTime>()
+        .expect("PlayTime resource is missing")
+        .seconds = metadata.play_time;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use lab_core::prelude::*;
+    use lab_entities::prelude::*;
+    use lab_sprites::{SpriteInfo, SpriteLibrary};
+
+    fn world() -> (World, Items, HandleAllocator) {
+        let mut world = World::new();
+        let mut items = Items::default();
+        let mut allocator = HandleAllocator::seeded(7);
+
+        let handle = allocator.next();
+        let item = world.spawn(ItemComponents {
+            name: Named("Stout".into()),
+            weight: Weight(1.),
+            handle,
+            ..Default::default()
+        });
+        items.items.insert(handle, item);
+
+        let player = world.spawn(PlayerComponents::new("Brewer"));
+        world
+            .insert(
+                player,
+                (
+                    Transform::from_translation(Vec3::new(16., 32., 35.)),
+                    Inventory(vec![handle]),
+                ),
+            )
+            .unwrap();
+
+        let mut state = ObjectState::default();
+        state.set_bool("open", false);
+
+        let tile = world.spawn(TileComponents {
+            name: Named("door".into()),
+            sprite: SpriteInfo {
+                name: "brick_door_closed".into(),
+                ..Default::default()
+            },
+            state,
+            interaction: InteractionKey::new(keys::BUMP),
+            ..Default::default()
+        });
+        world
+            .insert_one(tile, Transform::from_translation(Vec3::new(48., 0., 1.)))
+            .unwrap();
+
+        (world, items, allocator)
+    }
+
+    #[test]
+    fn saves_read_back_what_was_captured() {
+        let (world, items, allocator) = world();
+        let db = sled::Config::new().temporary(true).open().unwrap();
+
+        let mut save = SaveGame::capture(&world, &items);
+        save.handles = allocator;
+
+        assert!(save.player.is_some());
+        assert_eq!(save.tiles.len(), 1);
+        assert_eq!(save.items.len(), 1);
+
+        // overwriting goes through the same steps as a first write
+        save.write(&db, "slot").unwrap();
+        save.write(&db, "slot").unwrap();
+
+        let read = SaveGame::read(&db, "slot", &SaveMigrations::default()).unwrap();
+
+        assert_eq!(read, save);
+    }
+
+    #[test]
+    fn saves_restore_into_a_new_world() {
+        let (mut world, items, allocator) = world();
+        let db = sled::Config::new().temporary(true).open().unwrap();
+
+        let player = world.query::<(Entity, &Player)>().iter().next().unwrap().0;
+        world.get_mut::<Health>(player).unwrap().hurt(3);
+
+        let mut save = SaveGame::capture(&world, &items);
+        save.handles = allocator;
+        save.write(&db, "slot").unwrap();
+
+        let mut sprites = SpriteLibrary::new();
+        sprites.add(SpriteInfo {
+            name: "brick_door_closed".into(),
+            ..Default::default()
+        });
+
+        let mut restored = World::new();
+        let mut restored_items = Items::default();
+
+        SaveGame::read(&db, "slot", &SaveMigrations::default())
+            .unwrap()
+            .restore(&mut restored, &sprites, &mut restored_items);
+
+        let players: Vec<(Named, Inventory, Location, Health)> = restored
+            .query::<(&Player, &Named, &Inventory, &Location, &Health)>()
+            .iter()
+            .map(|(_, name, inventory, location, health)| {
+                (name.clone(), inventory.clone(), *location, *health)
+            })
+            .collect();
+        let handle = save.items[0].handle;
+
+        assert_eq!(players.len(), 1);
+        assert_eq!(players[0].0, Named("Brewer".into()));
+        assert_eq!(players[0].1, Inventory(vec![handle]));
+        assert_eq!(players[0].2, Location(16., 32., 35., WorldLocation::World));
+        assert_eq!(players[0].3, save.player.unwrap().health);
+
+        let tiles: Vec<(SpriteInfo, ObjectState, InteractionKey)> = restored
+            .query::<(&SpriteInfo, &ObjectState, &InteractionKey)>()
+            .iter()
+            .map(|(sprite, state, key)| (sprite.clone(), state.clone(), key.clone()))
+            .collect();
+
+        assert_eq!(tiles.len(), 1);
+        assert_eq!(tiles[0].0.name, "brick_door_closed");
+        assert!(!tiles[0].1.get_bool("open").unwrap());
+        assert_eq!(tiles[0].2, InteractionKey::new(keys::BUMP));
+
+        assert_eq!(restored_items.items.len(), 1);
+        let item = restored_items.items[&handle];
+        assert_eq!(*restored.get::<Named>(item).unwrap(), Named("Stout".into()));
+    }
+}
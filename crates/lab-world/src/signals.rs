@@ -0,0 +1,279 @@
+/// Module for wiring tiles together
+///
+/// Levers, pressure plates and buttons emit on named channels, doors, gates
+/// and traps listen to them. Every wired tile keeps whether it is active in
+/// its ObjectState, a StateChangeEvent for it sends a SignalEvent on each of
+/// its channels and the listeners work out their own state from there.
+use crate::{TileComponents, TileInteraction, TileInteractionResult, TilePalette};
+use lab_core::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// ObjectState key holding whether a wired tile is on
+pub const ACTIVE_STATE: &'static str = "active";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SignalKind {
+    /// Flips on and off when used
+    Lever,
+    /// On while something stands on it
+    Plate,
+    /// On for a moment when used, pair it with toggling listeners
+    Button,
+}
+
+/// How a listener combines the channels it listens to
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SignalLogic {
+    /// On when every channel is on
+    And,
+    /// On when any channel is on
+    Or,
+    /// Flips whenever a channel turns on
+    Toggle,
+}
+
+impl Default for SignalLogic {
+    fn default() -> Self {
+        SignalLogic::Or
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SignalWiring {
+    /// What makes the tile a source, listeners don't need one
+    #[serde(default)]
+    pub kind: Option<SignalKind>,
+    #[serde(default)]
+    pub emits: Vec<String>,
+    #[serde(default)]
+    pub listens: Vec<String>,
+    #[serde(default)]
+    pub logic: SignalLogic,
+    /// Tiles in the palette to show when the tile turns on or off
+    #[serde(default)]
+    pub active_tile: Option<String>,
+    #[serde(default)]
+    pub inactive_tile: Option<String>,
+}
+
+impl SignalWiring {
+    pub fn source(kind: SignalKind, channel: &str) -> SignalWiring {
+        SignalWiring {
+            kind: Some(kind),
+            emits: vec![channel.to_string()],
+            ..Default::default()
+        }
+    }
+
+    pub fn listener(logic: SignalLogic, channels: &[&str]) -> SignalWiring {
+        SignalWiring {
+            logic,
+            listens: channels.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_tiles(mut self, active_tile: &str, inactive_tile: &str) -> Self {
+        self.active_tile = Some(active_tile.to_string());
+        self.inactive_tile = Some(inactive_tile.to_string());
+        self
+    }
+
+    pub fn is_none(&self) -> bool {
+        self.emits.is_empty() && self.listens.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignalEvent {
+    pub channel: String,
+    pub active: bool,
+}
+
+#[derive(Default)]
+pub struct SignalState {
+    pub signal_events: EventReader<SignalEvent>,
+    pub state_changes: EventReader<StateChangeEvent>,
+}
+
+pub fn is_active(state: &ObjectState) -> bool {
+    state.get_bool(ACTIVE_STATE).unwrap_or(false)
+}
+
+/// Interaction for levers and buttons, using one switches it
+pub fn switch(
+    ctx: InteractionContext<TileInteraction, TileComponents, Vec<TileInteractionResult>>,
+) -> Vec<TileInteractionResult> {
+    let query = ctx.interaction_query;
+
+    let (kind, mut state) = match (
+        query.get::<SignalWiring>(ctx.destination),
+        query.get::<ObjectState>(ctx.destination),
+    ) {
+        (Ok(wiring), Ok(state)) => (wiring.kind, (*state).clone()),
+        _ => return TileInteractionResult::None.into(),
+    };
+
+    match (kind, &ctx.interaction_type) {
+        (Some(SignalKind::Plate), _) | (None, _) => TileInteractionResult::None.into(),
+        (Some(_), InteractionType::Collision) => TileInteractionResult::Block(ctx.source).into(),
+        (Some(SignalKind::Lever), InteractionType::Action(_)) => {
+            let active = !is_active(&state);
+
+            state.set_bool(ACTIVE_STATE, active);
+
+            TileInteractionResult::ChangeState(ctx.destination, state).into()
+        }
+        (Some(SignalKind::Button), InteractionType::Action(_)) => {
+            state.set_bool(ACTIVE_STATE, true);
+
+            TileInteractionResult::ChangeState(ctx.destination, state).into()
+        }
+    }
+}
+
+/// Interaction for doors and gates driven by signals, they only let you
+/// through when they are on
+pub fn gate(
+    ctx: InteractionContext<TileInteraction, TileComponents, Vec<TileInteractionResult>>,
+) -> Vec<TileInteractionResult> {
+    match ctx.interaction_query.get::<ObjectState>(ctx.destination) {
+        Ok(state) if is_active(&state) => TileInteractionResult::None.into(),
+        _ => vec![
+            TileInteractionResult::Block(ctx.source),
+            TileInteractionResult::Message(
+                "It won't budge, it must open from somewhere else".into(),
+            ),
+        ],
+    }
+}
+
+/// Pressure Plate System
+/// Plates are on while anything that moves stands on them.
+pub fn signal_plate_system(
+    index: Res<SpatialIndex>,
+    movers: Query<&Movement>,
+    mut plates: Query<(Entity, &SignalWiring, &mut ObjectState)>,
+) {
+    for (entity, wiring, mut state) in &mut plates.iter() {
+        if wiring.kind != Some(SignalKind::Plate) {
+            continue;
+        }
+
+        // only what is on top, not what is next to it
+        let pressed = index
+            .get(entity)
+            .map(|bounds| {
+                index
+                    .query_aabb(&Bounds::from_center(bounds.center(), bounds.size() / 2.))
+                    .into_iter()
+                    .any(|e| e != entity && movers.get::<Movement>(e).is_ok())
+            })
+            .unwrap_or(false);
+
+        if pressed != is_active(&state) {
+            state.set_bool(ACTIVE_STATE, pressed);
+        }
+    }
+}
+
+/// Signal System
+/// Sends a signal for every wired tile that turned on or off and works out
+/// the new state of the tiles listening to them.
+pub fn signal_system(
+    mut commands: Commands,
+    mut state: Local<SignalState>,
+    state_changes: Res<Events<StateChangeEvent>>,
+    mut signals: ResMut<Events<SignalEvent>>,
+    palette: Res<TilePalette>,
+    mut wired_query: Query<(Entity, &SignalWiring, &mut ObjectState)>,
+) {
+    let mut changed = Vec::new();
+
+    // tiles start out however they were placed, only changes are sent
+    for change in state.state_changes.iter(&state_changes) {
+        if change.key != ACTIVE_STATE {
+            continue;
+        }
+
+        let wiring = match wired_query.get::<SignalWiring>(change.entity) {
+            Ok(wiring) if !wiring.is_none() => (*wiring).clone(),
+            _ => continue,
+        };
+        let active = change.value == Some(StateValue::Bool(true));
+
+        // buttons spring back, their release goes out next frame
+        if active && wiring.kind == Some(SignalKind::Button) {
+            if let Ok(mut object_state) = wired_query.get_mut::<ObjectState>(change.entity) {
+                object_state.set_bool(ACTIVE_STATE, false);
+            }
+        }
+
+        changed.push((change.entity, wiring, active));
+    }
+
+    for (entity, wiring, active) in changed {
+        let tile = if active {
+            &wiring.active_tile
+        } else {
+            &wiring.inactive_tile
+        };
+
+        if let Some(tile) = tile.as_ref().and_then(|name| palette.components.get(name)) {
+            commands.insert(
+                entity,
+                (
+                    TextureAtlasSprite::new(tile.sprite.atlas_sprite),
+                    tile.sprite.clone(),
+                ),
+            );
+        }
+
+        for channel in wiring.emits.iter() {
+            signals.send(SignalEvent {
+                channel: channel.clone(),
+                active,
+            });
+        }
+    }
+
+    let events: Vec<SignalEvent> = state.signal_events.iter(&signals).cloned().collect();
+
+    if events.is_empty() {
+        return;
+    }
+
+    // a channel is on when any of its sources is
+    let mut levels: HashMap<String, bool> = HashMap::new();
+
+    for (_entity, wiring, object_state) in &mut wired_query.iter() {
+        for channel in wiring.emits.iter() {
+            *levels.entry(channel.clone()).or_insert(false) |= is_active(&object_state);
+        }
+    }
+
+    let level = |channel: &String| *levels.get(channel).unwrap_or(&false);
+
+    for event in events {
+        for (_entity, wiring, mut object_state) in &mut wired_query.iter() {
+            if !wiring.listens.contains(&event.channel) {
+                continue;
+            }
+
+            let current = is_active(&object_state);
+
+            let next = match wiring.logic {
+                SignalLogic::Toggle if event.active => !current,
+                SignalLogic::Toggle => current,
+                SignalLogic::And => wiring.listens.iter().all(level),
+                SignalLogic::Or => wiring.listens.iter().any(level),
+            };
+
+            // listeners that emit pass it on next frame
+            if next != current {
+                object_state.set_bool(ACTIVE_STATE, next);
+            }
+        }
+    }
+}
@@ -0,0 +1,290 @@
+/// Module for named save slots
+///
+/// All slots live in the same sled database. The `slots` tree holds the
+/// metadata of every slot, the records of a slot are stored in trees
+/// prefixed with the slot name (see `save::tree_name`).
+use crate::{save::*, SaveMigrations};
+use lab_core::prelude::*;
+use lab_entities::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SLOTS_TREE: &'static str = "slots";
+
+/// Slot used by the quick save / quick load keys when no slot is active
+pub const QUICKSAVE_SLOT: &'static str = "quicksave";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SlotMetadata {
+    pub slot: String,
+    pub character: Named,
+    pub job: Job,
+    /// Seconds played in this save
+    pub play_time: f64,
+    pub world_location: WorldLocation,
+    /// Seconds since the unix epoch when the slot was last written
+    pub timestamp: u64,
+}
+
+impl SlotMetadata {
+    pub fn new(slot: &str, save: &SaveGame, play_time: f64) -> SlotMetadata {
+        let (character, job, world_location) = match &save.player {
+            Some(player) => (player.name.clone(), player.job.clone(), player.location.3),
+            None => (Named::default(), Job::Undecided, WorldLocation::BarRoom),
+        };
+
+        SlotMetadata {
+            slot: slot.to_string(),
+            character,
+            job,
+            play_time,
+            world_location,
+            timestamp: now(),
+        }
+    }
+}
+
+/// Resource for listing, creating, copying and deleting save slots
+pub struct SaveSlots {
+    pub path: String,
+    /// Slot that was last saved to or loaded from
+    pub current: Option<String>,
+    db: Option<sled::Db>,
+}
+
+impl Default for SaveSlots {
+    fn default() -> Self {
+        SaveSlots::new("saves/labyrinth")
+    }
+}
+
+impl SaveSlots {
+    pub fn new(path: &str) -> SaveSlots {
+        SaveSlots {
+            path: path.to_string(),
+            current: None,
+            db: None,
+        }
+    }
+
+    /// The database is only opened once it is needed
+    fn db(&mut self) -> Result<&sled::Db, SaveError> {
+        if self.db.is_none() {
+            self.db = Some(sled::open(&self.path)?);
+        }
+
+        Ok(self.db.as_ref().unwrap())
+    }
+
+    /// All slots, most recently written first
+    pub fn list(&mut self) -> Result<Vec<SlotMetadata>, SaveError> {
+        let mut slots = Vec::new();
+
+        for entry in self.db()?.open_tree(SLOTS_TREE)?.iter() {
+            let (_key, value) = entry?;
+
+            slots.push(serde_json::from_slice::<SlotMetadata>(&value)?);
+        }
+
+        slots.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        Ok(slots)
+    }
+
+    /// The slot to offer for "Continue"
+    pub fn latest(&mut self) -> Result<Option<SlotMetadata>, SaveError> {
+        Ok(self.list()?.into_iter().next())
+    }
+
+    pub fn metadata(&mut self, slot: &str) -> Result<Option<SlotMetadata>, SaveError> {
+        match self.db()?.open_tree(SLOTS_TREE)?.get(slot)? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn exists(&mut self, slot: &str) -> Result<bool, SaveError> {
+        Ok(self.db()?.open_tree(SLOTS_TREE)?.contains_key(slot)?)
+    }
+
+    /// Create an empty slot for a new game
+    pub fn create(&mut self, slot: &str) -> Result<SlotMetadata, SaveError> {
+        if self.exists(slot)? {
+            return Err(SaveError::SlotExists(slot.to_string()));
+        }
+
+        let save = SaveGame {
+            version: SAVE_FORMAT_VERSION,
+            ..Default::default()
+        };
+        let metadata = SlotMetadata::new(slot, &save, 0.);
+
+        self.write(slot, &save, &metadata)?;
+
+        Ok(metadata)
+    }
+
+    /// Write the save into the slot, creating it if needed
+    pub fn save(&mut self, slot: &str, save: &SaveGame, play_time: f64) -> Result<(), SaveError> {
+        let metadata = SlotMetadata::new(slot, save, play_time);
+
+        self.write(slot, save, &metadata)?;
+        self.current = Some(slot.to_string());
+
+        Ok(())
+    }
+
+    pub fn load(
+        &mut self,
+        slot: &str,
+        migrations: &SaveMigrations,
+    ) -> Result<(SaveGame, SlotMetadata), SaveError> {
+        let metadata = self
+            .metadata(slot)?
+            .ok_or_else(|| SaveError::SlotNotFound(slot.to_string()))?;
+
+        let save = SaveGame::read(self.db()?, slot, migrations)?;
+
+        self.current = Some(slot.to_string());
+
+        Ok((save, metadata))
+    }
+
+    /// Copy every record of a slot into a new slot
+    pub fn copy(&mut self, from: &str, to: &str) -> Result<SlotMetadata, SaveError> {
+        let mut metadata = self
+            .metadata(from)?
+            .ok_or_else(|| SaveError::SlotNotFound(from.to_string()))?;
+
+        if self.exists(to)? {
+            return Err(SaveError::SlotExists(to.to_string()));
+        }
+
+        let db = self.db()?;
+
+        for tree in SAVE_TREES.iter() {
+            let source = db.open_tree(tree_name(from, tree))?;
+            let destination = db.open_tree(tree_name(to, tree))?;
+
+            for entry in source.iter() {
+                let (key, value) = entry?;
+                destination.insert(key, value)?;
+            }
+        }
+
+        metadata.slot = to.to_string();
+        metadata.timestamp = now();
+
+        db.open_tree(SLOTS_TREE)?
+            .insert(to, serde_json::to_vec(&metadata)?)?;
+        db.flush()?;
+
+        Ok(metadata)
+    }
+
+    pub fn delete(&mut self, slot: &str) -> Result<(), SaveError> {
+        if !self.exists(slot)? {
+            return Err(SaveError::SlotNotFound(slot.to_string()));
+        }
+
+        let db = self.db()?;
+
+        for tree in SAVE_TREES.iter() {
+            db.drop_tree(tree_name(slot, tree))?;
+        }
+
+        db.open_tree(SLOTS_TREE)?.remove(slot)?;
+        db.flush()?;
+
+        if self.current.as_deref() == Some(slot) {
+            self.current = None;
+        }
+
+        Ok(())
+    }
+
+    fn write(
+        &mut self,
+        slot: &str,
+        save: &SaveGame,
+        metadata: &SlotMetadata,
+    ) -> Result<(), SaveError> {
+        let db = self.db()?;
+
+        save.write(db, slot)?;
+
+        db.open_tree(SLOTS_TREE)?
+            .insert(slot, serde_json::to_vec(metadata)?)?;
+        db.flush()?;
+
+        Ok(())
+    }
+}
+
+/// Time spent playing the current game
+#[derive(Default, Debug)]
+pub struct PlayTime {
+    pub seconds: f64,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn slots_can_be_created_copied_and_deleted() {
+        let path = std::env::temp_dir().join(format!("labyrinth-slots-{}", std::process::id()));
+        let mut slots = SaveSlots::new(&path.to_string_lossy());
+
+        assert!(slots.list().unwrap().is_empty());
+        assert_eq!(slots.latest().unwrap(), None);
+
+        let created = slots.create("first").unwrap();
+        assert_eq!(created.slot, "first");
+        assert!(matches!(
+            slots.create("first"),
+            Err(SaveError::SlotExists(_))
+        ));
+        assert_eq!(slots.latest().unwrap(), Some(created));
+
+        let copy = slots.copy("first", "second").unwrap();
+        assert_eq!(copy.slot, "second");
+        assert!(matches!(
+            slots.copy("missing", "third"),
+            Err(SaveError::SlotNotFound(_))
+        ));
+
+        let mut listed: Vec<String> = slots.list().unwrap().into_iter().map(|m| m.slot).collect();
+        listed.sort();
+        assert_eq!(listed, vec!["first", "second"]);
+
+        let (save, _) = slots.load("second", &SaveMigrations::default()).unwrap();
+        assert_eq!(save.version, SAVE_FORMAT_VERSION);
+        assert_eq!(slots.current.as_deref(), Some("second"));
+
+        slots.delete("first").unwrap();
+        assert!(!slots.exists("first").unwrap());
+        assert!(matches!(
+            slots.delete("first"),
+            Err(SaveError::SlotNotFound(_))
+        ));
+        assert_eq!(
+            slots.latest().unwrap().map(|m| m.slot),
+            Some("second".to_string())
+        );
+
+        slots.delete("second").unwrap();
+        assert_eq!(slots.current, None);
+        assert!(slots.list().unwrap().is_empty());
+
+        drop(slots);
+        std::fs::remove_dir_all(path).ok();
+    }
+}
@@ -0,0 +1,78 @@
+/// Module for lasting effects on entities
+///
+/// Statuses are added through TileInteractionResult::Status and wear off on
+/// their own. While they last they can keep producing results, poison for
+/// example hurts every second.
+use crate::{TileInteractionResult, TileInteractionResultEvent};
+use lab_core::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum StatusKind {
+    /// Hurts a little every second
+    Poisoned,
+    /// Lost footing, nothing happens beyond the slide that caused it
+    Slipping,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub kind: StatusKind,
+    /// Seconds left before it wears off
+    pub seconds: f32,
+}
+
+impl Status {
+    pub fn new(kind: StatusKind, seconds: f32) -> Status {
+        Status { kind, seconds }
+    }
+}
+
+/// Component holding the statuses an entity is under
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Statuses(pub Vec<Status>);
+
+impl Statuses {
+    /// Add the status, one of the same kind is topped up instead
+    pub fn add(&mut self, status: Status) {
+        match self.0.iter_mut().find(|s| s.kind == status.kind) {
+            Some(existing) => existing.seconds = existing.seconds.max(status.seconds),
+            None => self.0.push(status),
+        }
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.0.iter().any(|s| s.kind == kind)
+    }
+}
+
+/// Status System
+/// Wears statuses off and applies what they do while they last.
+pub fn status_system(
+    time: Res<Time>,
+    mut results: ResMut<Events<TileInteractionResultEvent>>,
+    mut query: Query<(Entity, &mut Statuses)>,
+) {
+    for (entity, mut statuses) in &mut query.iter() {
+        if statuses.0.is_empty() {
+            continue;
+        }
+
+        for status in statuses.0.iter_mut() {
+            let before = status.seconds;
+
+            status.seconds -= time.delta_seconds;
+
+            // a whole second went by
+            if status.kind == StatusKind::Poisoned && before.ceil() != status.seconds.ceil() {
+                results.send(TileInteractionResultEvent::new(
+                    entity,
+                    entity,
+                    TileInteractionResult::Damage(entity, entity, 1.),
+                ));
+            }
+        }
+
+        statuses.0.retain(|status| status.seconds > 0.);
+    }
+}
@@ -1,12 +1,17 @@
-use bevy::sprite::collide_aabb::*;
-
 use crate::{
-    InteractionState, TextChangeEvent, TileComponents, TileInteraction, TileInteractionResult,
-    TileInteractionResultEvent, UiTextState,
+    damage_after_hardness, durability, item_sprite, keys, load_world, save_world, settings,
+    ActionState, Concealment, Destructible, InteractionHandlers, InteractionState, InventoryPolicy,
+    LevelChangeEvent, LevelChangeState, Lock, PlayTime, SaveSlots, SaveState, SaveWorldEvent,
+    SpatialIndexState, Statuses, TextChangeEvent, TileComponents, TileInteraction,
+    TileInteractionResult, TileInteractionResultEvent, TilePalette, TurnState, UiTextState,
+    HIT_POINTS_STATE, QUICKSAVE_SLOT,
 };
 use lab_core::prelude::*;
+use lab_data::ItemDefinition;
 use lab_entities::prelude::*;
-use lab_sprites::SpriteInfo;
+use lab_input::{actions, PlayerActionEvent};
+use lab_sprites::{SpriteInfo, SpriteLibrary};
+use std::collections::{HashSet, VecDeque};
 
 pub fn camera_tracking_system(
     mut player_moved: Query<With<Player, (Entity, Mutated<Transform>)>>,
@@ -22,49 +27,352 @@ pub fn camera_tracking_system(
     }
 }
 /// Collision System
-/// This system sends an event whenever two Interactables touch.
+/// This system sends an event whenever a mover's Collider runs into the Collider
+/// of an Interactable. Only the entities the SpatialIndex has near the mover are checked.
 pub fn collision_system(
     mut interaction_event: ResMut<Events<InteractionEvent>>,
-    mut wall_query: Query<(Entity, &Transform, &Size, &InteractableType)>,
-    mut moveables: Query<(Entity, Mutated<Transform>)>,
+    index: Res<SpatialIndex>,
+    wall_query: Query<(Entity, &Transform, &Collider, &InteractableType)>,
+    step_query: Query<&GridStep>,
+    mut moveables: Query<With<Movement, (Entity, &Collider, Mutated<Transform>)>>,
 ) {
-    for (mov_entity, move_translation) in &mut moveables.iter() {
-        //println!("checking colision move: {:?}", *move_translation);
-        for (destination_entity, tile_translation, size, interact_type) in
-            &mut wall_query.iter()
-        {
-            //println!("Tile: {:?} {:?}", tile_translation, size);
-            if *interact_type == InteractableType::None {
-                continue;
-            }
-            if mov_entity == destination_entity {
-                continue;
-            }
+    for (mov_entity, collider, move_translation) in &mut moveables.iter() {
+        // grid steps were checked before they were taken
+        if step_query.get::<GridStep>(mov_entity).is_ok() {
+            continue;
+        }
 
-            //println!("checking colision tile: {:?}", tile_translation);
-            let collision = collide(
-                move_translation.translation(),
-                Vec2::new(8., 16.) * move_translation.scale().truncate(),
-                Vec3::new(
-                    tile_translation.translation().x() + (size.width/2.), 
-                    tile_translation.translation().y(), 0.),
-                Vec2::new(size.width, size.height)
-            );
-
-            if let Some(collision) = collision {
-                //println!("Collision detected {}", move_translation.translation());
-                match collision {
-                    _ => interaction_event.send(InteractionEvent {
-                        source: mov_entity,
-                        destination: destination_entity,
-                        interaction_type: InteractionType::Collision,
-                    }),
+        let area = collider.bounds(&move_translation);
+
+        for destination_entity in colliding(&index, &wall_query, mov_entity, &area) {
+            interaction_event.send(InteractionEvent {
+                source: mov_entity,
+                destination: destination_entity,
+                interaction_type: InteractionType::Collision,
+            });
+        }
+    }
+}
+
+/// Interactables whose Collider the area runs into, other than the mover itself
+fn colliding(
+    index: &SpatialIndex,
+    wall_query: &Query<(Entity, &Transform, &Collider, &InteractableType)>,
+    mover: Entity,
+    area: &Bounds,
+) -> Vec<Entity> {
+    index
+        .query_aabb(area)
+        .into_iter()
+        .filter(|entity| *entity != mover)
+        .filter(|entity| {
+            match (
+                wall_query.get::<Transform>(*entity),
+                wall_query.get::<Collider>(*entity),
+                wall_query.get::<InteractableType>(*entity),
+            ) {
+                (Ok(transform), Ok(collider), Ok(interact_type)) => {
+                    *interact_type != InteractableType::None
+                        && area.intersects(&collider.bounds(&transform))
                 }
+                _ => false,
             }
+        })
+        .collect()
+}
+
+/// Grid Movement System
+/// Sends a collision to whatever is on the tile a step goes to, then moves the
+/// mover there over the length of the step. A Block from the tile cancels it.
+pub fn grid_movement_system(
+    mut commands: Commands,
+    time: Res<Time>,
+    index: Res<SpatialIndex>,
+    mut interaction_event: ResMut<Events<InteractionEvent>>,
+    wall_query: Query<(Entity, &Transform, &Collider, &InteractableType)>,
+    mut step_query: Query<(Entity, &mut GridStep, &mut Transform, &Movement, &Collider)>,
+) {
+    for (entity, mut step, mut transform, movement, collider) in &mut step_query.iter() {
+        if !step.checked {
+            step.checked = true;
+
+            let area = collider.bounds_at(step.to.truncate(), transform.scale().truncate());
+
+            for destination in colliding(&index, &wall_query, entity, &area) {
+                interaction_event.send(InteractionEvent {
+                    source: entity,
+                    destination,
+                    interaction_type: InteractionType::Collision,
+                });
+            }
+
+            continue;
+        }
+
+        if movement.legal == Some(false) {
+            transform.set_translation(step.from);
+            commands.remove_one::<GridStep>(entity);
+            continue;
+        }
+
+        step.elapsed += time.delta_seconds;
+        transform.set_translation(step.position());
+
+        if step.finished() {
+            commands.remove_one::<GridStep>(entity);
         }
     }
 }
 
+/// Collider System
+/// Gives sprites a Collider the size of the sprite, movers get theirs inset.
+pub fn collider_system(
+    mut commands: Commands,
+    mut sprite_query: Query<Without<Collider, (Entity, &SpriteInfo, &InteractableType)>>,
+    mover_query: Query<&Movement>,
+) {
+    for (entity, sprite, _interactable_type) in &mut sprite_query.iter() {
+        let mut collider = sprite.collider();
+
+        if mover_query.get::<Movement>(entity).is_ok() {
+            collider = collider.with_insets(Vec2::new(settings::MOVER_INSET, 0.));
+        }
+
+        commands.insert_one(entity, collider);
+    }
+}
+
+/// Spatial Index System
+/// Keeps the SpatialIndex up to date with the entities that were spawned, moved
+/// or removed this frame.
+pub fn spatial_index_system(
+    mut state: Local<SpatialIndexState>,
+    mut index: ResMut<SpatialIndex>,
+    despawned_events: Res<Events<EntityDespawnedEvent>>,
+    // the collider is what gets bumped into, it wins over the sprite
+    mut sprite_query: Query<Without<Collider, (Entity, &SpriteInfo, Changed<Transform>)>>,
+    mut collider_query: Query<(Entity, &Collider, Changed<Transform>)>,
+    // tiles given a collider after they were placed don't move again
+    mut new_collider_query: Query<(Entity, Added<Collider>, &Transform)>,
+    removed_query: Query<&Transform>,
+) {
+    for event in state.despawned_events.iter(&despawned_events) {
+        index.remove(event.entity);
+    }
+
+    for entity in removed_query.removed::<Transform>().iter() {
+        index.remove(*entity);
+    }
+
+    // entities with a collider, like the player and tiled objects, by their collider
+    for (entity, collider, transform) in &mut collider_query.iter() {
+        index.insert(entity, collider.bounds(&transform));
+    }
+
+    for (entity, collider, transform) in &mut new_collider_query.iter() {
+        index.insert(entity, collider.bounds(transform));
+    }
+
+    for (entity, sprite, transform) in &mut sprite_query.iter() {
+        index.insert(entity, sprite_bounds(sprite, &transform));
+    }
+}
+
+/// Player Action System
+/// Turns a player action into an Action interaction with the interactable the
+/// player is facing or, failing that, the closest one next to the player.
+pub fn player_action_system(
+    mut state: Local<ActionState>,
+    action_events: Res<Events<PlayerActionEvent>>,
+    mut interaction_event: ResMut<Events<InteractionEvent>>,
+    index: Res<SpatialIndex>,
+    settings: Res<WorldSettings>,
+    actor_query: Query<(&Transform, &Facing)>,
+    target_query: Query<(Entity, &Transform, &InteractableType, &InteractionKey)>,
+) {
+    for event in state.action_events.iter(&action_events) {
+        // searching isn't aimed at anything, see concealment_system
+        if event.action == actions::SEARCH {
+            continue;
+        }
+
+        let (position, facing, tile) = match (
+            actor_query.get::<Transform>(event.entity),
+            actor_query.get::<Facing>(event.entity),
+        ) {
+            (Ok(transform), Ok(facing)) => (
+                transform.translation().truncate(),
+                *facing,
+                settings.tile(transform.scale().x()),
+            ),
+            _ => continue,
+        };
+        let reach = tile.x();
+
+        let usable = |entity: &Entity| {
+            *entity != event.entity
+                && target_query
+                    .get::<InteractionKey>(*entity)
+                    .map(|key| !key.is_none())
+                    .unwrap_or(false)
+        };
+
+        let faced = index
+            .query_point(
+                TileCoord::from_world(position, tile)
+                    .neighbor(facing.0)
+                    .to_world(tile),
+            )
+            .into_iter()
+            .filter(|e| usable(e))
+            .max_by(|a, b| z_order(&target_query, *a, *b));
+
+        let target = faced.or_else(|| {
+            let around = Bounds::from_center(position, Vec2::new(reach * 2., reach * 2.));
+
+            index
+                .query_aabb(&around)
+                .into_iter()
+                .filter(|e| usable(e))
+                .min_by(|a, b| {
+                    let distance = |e: &Entity| {
+                        index
+                            .get(*e)
+                            .map(|bounds| (bounds.center() - position).length())
+                            .unwrap_or(std::f32::MAX)
+                    };
+
+                    distance(a)
+                        .partial_cmp(&distance(b))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                })
+        });
+
+        if let Some(destination) = target {
+            interaction_event.send(InteractionEvent {
+                source: event.entity,
+                destination,
+                interaction_type: InteractionType::Action(event.action.clone()),
+            });
+        }
+    }
+}
+
+/// Turn System
+/// Keeps every entity with a Speed in the turn scheduler. In turn mode a player
+/// taking a step or acting lets everyone else take the turns they earned.
+pub fn turn_system(
+    mut state: Local<TurnState>,
+    settings: Res<WorldSettings>,
+    mut turns: ResMut<Turns>,
+    action_events: Res<Events<PlayerActionEvent>>,
+    mut turn_events: ResMut<Events<TurnEvent>>,
+    mut actor_query: Query<(Entity, &Speed)>,
+    mut step_query: Query<With<Player, (Entity, Added<GridStep>)>>,
+) {
+    for entity in actor_query.removed::<Speed>().iter() {
+        turns.remove(*entity);
+    }
+
+    for (entity, speed) in &mut actor_query.iter() {
+        turns.add(entity, *speed);
+    }
+
+    let mut acted: Vec<Entity> = state
+        .action_events
+        .iter(&action_events)
+        .map(|event| event.entity)
+        .collect();
+
+    for (entity, _step) in &mut step_query.iter() {
+        acted.push(entity);
+    }
+
+    if !settings.turn_mode {
+        return;
+    }
+
+    for player in acted {
+        for entity in turns.player_acted(player) {
+            turn_events.send(TurnEvent { entity });
+        }
+    }
+}
+
+/// State Change System
+/// Sends an event for every key of an ObjectState that changed this frame,
+/// so nothing has to compare whole states to notice e.g. a door opening.
+pub fn state_change_system(
+    mut change_events: ResMut<Events<StateChangeEvent>>,
+    mut new_query: Query<(Entity, Added<ObjectState>)>,
+    mut state_query: Query<(Entity, &mut ObjectState)>,
+) {
+    // what a state starts out with isn't a change
+    let new: HashSet<Entity> = (&mut new_query.iter()).map(|(entity, _)| entity).collect();
+
+    for (entity, mut object_state) in &mut state_query.iter() {
+        // only look through Mut when there is something to take
+        if !object_state.has_changes() {
+            continue;
+        }
+
+        let changes = object_state.take_changes();
+
+        if new.contains(&entity) {
+            continue;
+        }
+
+        for key in changes {
+            change_events.send(StateChangeEvent {
+                entity,
+                value: object_state.get(&key).ok().cloned(),
+                key,
+            });
+        }
+    }
+}
+
+/// Mob Loot System
+/// Hands new mobs the items they carry, they are made from the ItemCatalog.
+pub fn mob_loot_system(
+    mut results: ResMut<Events<TileInteractionResultEvent>>,
+    mut loot_query: Query<(Entity, Added<MobLoot>)>,
+) {
+    for (entity, loot) in &mut loot_query.iter() {
+        for id in loot.0.iter() {
+            results.send(TileInteractionResultEvent::new(
+                entity,
+                entity,
+                TileInteractionResult::SpawnItem(entity, id.clone()),
+            ));
+        }
+    }
+}
+
+/// Order entities by their z translation, the top most is the one being used
+fn z_order(
+    query: &Query<(Entity, &Transform, &InteractableType, &InteractionKey)>,
+    a: Entity,
+    b: Entity,
+) -> std::cmp::Ordering {
+    let z = |e: Entity| {
+        query
+            .get::<Transform>(e)
+            .map(|t| t.translation().z())
+            .unwrap_or(0.)
+    };
+
+    z(a).partial_cmp(&z(b)).unwrap_or(std::cmp::Ordering::Equal)
+}
+
+/// Sprites are drawn centered on their translation
+pub fn sprite_bounds(sprite: &SpriteInfo, transform: &Transform) -> Bounds {
+    Bounds::from_center(
+        transform.translation().truncate(),
+        sprite.size() * transform.scale().x(),
+    )
+}
+
 /// Interaction System
 /// This sytem will try and see if there is a registered Interaction handler for a
 /// object that has experienced a collision. If there is, it is ran
@@ -72,52 +380,81 @@ pub fn collision_system(
 
 pub fn interaction_system(
     items: ResMut<Items>,
+    settings: Res<WorldSettings>,
     mut result_events: ResMut<Events<TileInteractionResultEvent>>,
     mut state: ResMut<InteractionState>,
     interaction_events: ResMut<Events<InteractionEvent>>,
     world_catalog: Res<
         InteractionCatalog<TileInteraction, TileComponents, Vec<TileInteractionResult>>,
     >,
-    item_query: Query<(Entity, &ItemType, &Named)>,
+    item_query: Query<(Entity, &ItemType, &Named, &ItemDefinition)>,
     interactable_query: Query<(
         Entity,
         &InteractableType,
         &ObjectState,
-        &TileInteraction,
+        &InteractionKey,
         &Inventory
     )>,
 ) {
     for event in state.interaction_events.iter(&interaction_events) {
         match event.interaction_type {
-            InteractionType::Collision => {
+            InteractionType::Collision | InteractionType::Action(_) => {
                 if event.source == event.destination {
-                    panic!("A entity collided with itself, this should not happen")
+                    panic!("A entity interacted with itself, this should not happen")
+                }
+
+                // hidden tiles are nothing but wall until they are found
+                if let Ok(concealment) = interactable_query.get::<Concealment>(event.destination) {
+                    if concealment.hidden {
+                        if let InteractionType::Collision = event.interaction_type {
+                            result_events.send(TileInteractionResultEvent::new(
+                                event.source,
+                                event.destination,
+                                TileInteractionResult::Block(event.source),
+                            ));
+                        }
+
+                        continue;
+                    }
                 }
 
-                let tile_handle_r =
-                    interactable_query.get::<TileInteraction>(event.destination);
+                let ctx = InteractionContext {
+                    source: event.source,
+                    destination: event.destination,
+                    interaction_type: event.interaction_type.clone(),
+                    world_catalog: &world_catalog,
+                    interaction_query: &interactable_query,
+                    item_query: &item_query,
+                    items: &items,
+                    settings: &settings,
+                };
+
+                // handlers go first, they can stop the keyed interaction from running
+                let (mut results, stopped) = match interactable_query
+                    .get::<InteractionHandlers>(event.destination)
+                {
+                    Ok(handlers) => handlers.run(&ctx),
+                    Err(_) => (Vec::new(), false),
+                };
 
-                if let Some(tile_interaction) = tile_handle_r.ok() {
+                let tile_interaction = interactable_query
+                    .get::<InteractionKey>(event.destination)
+                    .ok()
+                    .and_then(|key| world_catalog.get_interaction_by_key(&*key));
+
+                if let (false, Some(tile_interaction)) = (stopped, tile_interaction) {
                     //println!("{:?} interacted with {:?} name: {:?}", event.source, event.destination, tile_interaction);
+                    results.extend(tile_interaction.interact(ctx));
+                }
 
-                    let ctx = InteractionContext {
+                for r in results.into_iter() {
+                    result_events.send(TileInteractionResultEvent {
                         source: event.source,
                         destination: event.destination,
-                        world_catalog: world_catalog.clone(),
-                        interaction_query: &interactable_query,
-                        item_query: &item_query,
-                        items: &items,
-                    };
-                    for r in tile_interaction.interact(ctx).iter() {
-                        result_events.send(TileInteractionResultEvent {
-                            _source: event.source,
-                            destination: event.destination,
-                            result: r.clone(),
-                        })
-                    }
+                        result: r,
+                    })
                 }
             }
-            _ => {}
         }
     }
 }
@@ -126,9 +463,33 @@ pub fn process_interaction_result_system(
     mut commands: Commands,
     interaction_events: ResMut<Events<TileInteractionResultEvent>>,
     mut items: ResMut<Items>,
+    mut allocator: ResMut<HandleAllocator>,
     mut state: ResMut<InteractionState>,
     mut text_update: ResMut<Events<TextChangeEvent>>,
+    mut despawned: ResMut<Events<EntityDespawnedEvent>>,
+    mut level_changes: ResMut<Events<LevelChangeEvent>>,
+    sprites: Res<SpriteLibrary>,
+    palette: Res<TilePalette>,
+    catalog: Res<ItemCatalog>,
     _tile_query: Query<(Entity, &Draw)>,
+    inventory_query: Query<(&Inventory, &Transform)>,
+    // tiles don't move, their state can't be reached through the entity query
+    state_query: Query<&mut ObjectState>,
+    destructible_query: Query<&Destructible>,
+    // pushed objects have no inventory or draw of their own to match the entity query
+    transform_query: Query<&mut Transform>,
+    movement_query: Query<&mut Movement>,
+    collider_query: Query<&Collider>,
+    statuses_query: Query<&mut Statuses>,
+    health_query: Query<(&mut Health, Option<&NonPlayer>)>,
+    item_query: Query<(
+        &Named,
+        &Weight,
+        &ItemType,
+        &ItemSlot,
+        &ItemDefinition,
+        &WorldHandle<Item>,
+    )>,
     entity_query: Query<(
         Entity,
         &mut Transform,
@@ -137,16 +498,105 @@ pub fn process_interaction_result_system(
         &mut Draw,
     )>,
 ) {
-    for event in state.interaction_results.iter(&interaction_events) {
-        match event.result.clone() {
+    // every result along with what it came from
+    let mut results: VecDeque<(Entity, TileInteractionResult)> = state
+        .interaction_results
+        .iter(&interaction_events)
+        .map(|event| (event.destination, event.result.clone()))
+        .collect();
+
+    // a result can lead to more, e.g. damage destroying a tile
+    while let Some((origin, result)) = results.pop_front() {
+        match result {
             TileInteractionResult::ChangeSprite(entity, sprite_info) => {
-                commands.insert(entity, (TextureAtlasSprite::new(sprite_info.atlas_sprite),));
+                commands.insert(
+                    entity,
+                    (
+                        TextureAtlasSprite::new(sprite_info.atlas_sprite),
+                        sprite_info,
+                    ),
+                );
             }
             TileInteractionResult::Damage(_src, dst, amount) => {
-                if let Ok(mut state) = entity_query.get_mut::<ObjectState>(dst) {
-                    let hp: i32 = state.get("hitpoints".into()).unwrap().into();
+                // players and mobs take damage to their health, tiles to their durability
+                if let Ok(mut health) = health_query.get_mut::<Health>(dst) {
+                    let was_down = health.is_down();
+
+                    health.hurt(damage_after_hardness(amount, 0));
+
+                    if health.is_down() && !was_down {
+                        let result = if health_query.get::<NonPlayer>(dst).is_ok() {
+                            TileInteractionResult::Despawn(dst, InventoryPolicy::Drop)
+                        } else {
+                            TileInteractionResult::Message("You collapse!".into())
+                        };
+
+                        results.push_back((dst, result));
+                    }
 
-                    state.set_int("hitpoints".into(), hp - amount as i32);
+                    continue;
+                }
+
+                let mut object_state = match state_query.get_mut::<ObjectState>(dst) {
+                    Ok(object_state) => object_state,
+                    Err(_) => continue,
+                };
+
+                // without hit points there is nothing to break
+                let (hit_points, hardness) = match durability(&object_state) {
+                    Some(durability) => durability,
+                    None => continue,
+                };
+                let remaining = (hit_points - damage_after_hardness(amount, hardness)).max(0);
+
+                object_state.set_int(HIT_POINTS_STATE, remaining);
+
+                let destructible = destructible_query
+                    .get::<Destructible>(dst)
+                    .map(|d| (*d).clone())
+                    .unwrap_or_default();
+
+                if remaining == 0 {
+                    let rubble = destructible
+                        .rubble
+                        .as_ref()
+                        .and_then(|name| palette.components.get(name).map(|tile| (name, tile)));
+
+                    match rubble {
+                        Some((name, rubble)) => {
+                            // rubble can't be broken any further and is walked over
+                            object_state.remove(HIT_POINTS_STATE);
+
+                            commands.insert(
+                                dst,
+                                (
+                                    TextureAtlasSprite::new(rubble.sprite.atlas_sprite),
+                                    rubble.sprite.clone(),
+                                    Named(name.clone()),
+                                    InteractableType::None,
+                                    InteractionKey::new(keys::NONE),
+                                    InteractionHandlers::default(),
+                                    Lock::default(),
+                                    Destructible::default(),
+                                ),
+                            );
+                        }
+                        None => {
+                            results.push_back((
+                                dst,
+                                TileInteractionResult::Despawn(dst, InventoryPolicy::Drop),
+                            ));
+                        }
+                    }
+                } else if let Some(stage) = destructible.stage_at(remaining) {
+                    if destructible.stage_at(hit_points) != Some(stage) {
+                        if let Some(tile) = palette.components.get(&stage.tile) {
+                            results.push_back((
+                                dst,
+                                TileInteractionResult::ChangeSprite(dst, tile.sprite.clone()),
+                            ));
+                        }
+                    }
                 }
             }
             TileInteractionResult::ChangeInventory(entity, inv) => {
@@ -156,27 +606,116 @@ pub fn process_interaction_result_system(
             }
             TileInteractionResult::ChangeState(entity, state) => {
                 // commit state changes in this
-                if let Ok(mut dstate) = entity_query.get_mut::<ObjectState>(entity) {
-                    dstate.values = state.values;
+                if let Ok(mut dstate) = state_query.get_mut::<ObjectState>(entity) {
+                    dstate.replace(state);
                 }
             }
             TileInteractionResult::Move(entity, location) => {
-                if let Ok(mut new_location) = entity_query.get_mut::<Transform>(entity) {
+                if let Ok(mut new_location) = transform_query.get_mut::<Transform>(entity) {
+                    let start = new_location.translation();
+
                     *new_location.translation_mut().x_mut() = location.0;
                     *new_location.translation_mut().y_mut() = location.1;
+
+                    // a Block from wherever it lands puts it back
+                    if let Ok(mut movement) = movement_query.get_mut::<Movement>(entity) {
+                        *movement = Movement::new(
+                            start,
+                            new_location.translation(),
+                            new_location.translation() - start,
+                        );
+                    }
+                }
+            }
+            TileInteractionResult::Despawn(entity, policy) => {
+                let carried = inventory_query
+                    .get::<Inventory>(entity)
+                    .map(|inventory| inventory.0.clone())
+                    .unwrap_or_default();
+                let location = inventory_query
+                    .get::<Transform>(entity)
+                    .map(|transform| (transform.translation(), transform.scale().x()))
+                    .ok();
+
+                for handle in carried.iter() {
+                    let item = match items.items.remove(handle) {
+                        Some(item) => item,
+                        None => continue,
+                    };
+
+                    if let (InventoryPolicy::Drop, Some((translation, scale))) = (policy, location) {
+                        drop_item(&mut commands, &sprites, &item_query, item, translation, scale);
+                    }
+
+                    commands.despawn_recursive(item);
+                }
+
+                // the entity might have been an item itself
+                items.items.retain(|_, item| *item != entity);
+
+                commands.despawn_recursive(entity);
+                despawned.send(EntityDespawnedEvent { entity });
+            }
+            TileInteractionResult::RemoveItem(holder, handle) => {
+                if let Ok(mut inventory) = entity_query.get_mut::<Inventory>(holder) {
+                    inventory.0.retain(|h| *h != handle);
+                }
+
+                if let Some(item) = items.items.remove(&handle) {
+                    commands.despawn_recursive(item);
+                }
+            }
+            TileInteractionResult::Status(entity, status) => {
+                match statuses_query.get_mut::<Statuses>(entity) {
+                    Ok(mut statuses) => statuses.add(status),
+                    Err(_) => {
+                        commands.insert_one(entity, Statuses(vec![status]));
+                    }
                 }
             }
-            TileInteractionResult::Despawn => {
-                commands.remove_one::<Draw>(event.destination);
-                commands.remove_one::<Transform>(event.destination);
+            TileInteractionResult::ChangeLevel(entity, levels) => {
+                level_changes.send(LevelChangeEvent { entity, levels });
             }
             TileInteractionResult::Block(entity) => {
-                if let Ok(mut translation) = entity_query.get_mut::<Transform>(entity) {
-                    if let Ok(src_move) = entity_query.get::<Movement>(entity) {
+                let blocker = if origin != entity {
+                    match (
+                        transform_query.get::<Transform>(origin),
+                        collider_query.get::<Collider>(origin),
+                    ) {
+                        (Ok(transform), Ok(collider)) => Some(collider.bounds(&transform)),
+                        _ => None,
+                    }
+                } else {
+                    None
+                };
+
+                if let (Ok(mut translation), Ok(src_move)) = (
+                    transform_query.get_mut::<Transform>(entity),
+                    movement_query.get::<Movement>(entity),
+                ) {
+                    let collider = collider_query.get::<Collider>(entity);
+                    let scale = translation.scale().truncate();
+
+                    let (block_x, block_y) = match (blocker, collider) {
+                        (Some(blocker), Ok(collider)) => {
+                            blocked_axes(&collider, &src_move, scale, &blocker)
+                        }
+                        _ => (true, true),
+                    };
+
+                    // only the axis that ran into it is undone, the mover slides along
+                    if block_x {
                         *translation.translation_mut().x_mut() = src_move.start.x();
+                    }
+                    if block_y {
                         *translation.translation_mut().y_mut() = src_move.start.y();
                     }
                 }
+
+                // grid steps that haven't been taken yet are called off
+                if let Ok(mut movement) = movement_query.get_mut::<Movement>(entity) {
+                    movement.legal = Some(false);
+                }
             }
             TileInteractionResult::None => {}
             TileInteractionResult::Log(_) => {}
@@ -188,9 +727,14 @@ pub fn process_interaction_result_system(
             }
             TileInteractionResult::Menu(_) => {}
 
-            TileInteractionResult::AddItem(dst, item) => {
+            TileInteractionResult::AddItem(dst, mut item) => {
                 // Add an item to the destination entities inventory
                 if let Ok(mut inventory) = entity_query.get_mut::<Inventory>(dst) {
+                    // interactions don't know about the allocator, give the item its identity here
+                    if item.handle.is_nil() {
+                        item.handle = allocator.next();
+                    }
+
                     inventory.0.push(item.handle.clone());
 
                     let handle = item.handle;
@@ -201,12 +745,175 @@ pub fn process_interaction_result_system(
                     });
                 }
             }
+            TileInteractionResult::SpawnItem(dst, id) => match catalog.get(&id) {
+                Some(definition) => {
+                    let item = ItemComponents::from(definition);
+                    results.push_back((origin, TileInteractionResult::AddItem(dst, item)));
+                }
+                None => log::warn!("No item {} in the item catalog", id),
+            },
+        };
+    }
+}
+
+/// Which axes of the move ran into the blocker, both when only the whole move does
+fn blocked_axes(
+    collider: &Collider,
+    movement: &Movement,
+    scale: Vec2,
+    blocker: &Bounds,
+) -> (bool, bool) {
+    let (start, end) = (movement.start.truncate(), movement.end.truncate());
+
+    let block_x = blocker.intersects(&collider.bounds_at(Vec2::new(end.x(), start.y()), scale));
+    let block_y = blocker.intersects(&collider.bounds_at(Vec2::new(start.x(), end.y()), scale));
+
+    if block_x || block_y {
+        (block_x, block_y)
+    } else {
+        (true, true)
+    }
+}
+
+/// Leave an item from an inventory lying in the world, it can be picked up again
+fn drop_item(
+    commands: &mut Commands,
+    sprites: &SpriteLibrary,
+    item_query: &Query<(
+        &Named,
+        &Weight,
+        &ItemType,
+        &ItemSlot,
+        &ItemDefinition,
+        &WorldHandle<Item>,
+    )>,
+    item: Entity,
+    translation: Vec3,
+    scale: f32,
+) {
+    if let (Ok(name), Ok(weight), Ok(item_type), Ok(item_slot), Ok(definition), Ok(handle)) = (
+        item_query.get::<Named>(item),
+        item_query.get::<Weight>(item),
+        item_query.get::<ItemType>(item),
+        item_query.get::<ItemSlot>(item),
+        item_query.get::<ItemDefinition>(item),
+        item_query.get::<WorldHandle<Item>>(item),
+    ) {
+        let sprite = match sprites.get(item_sprite(&definition)) {
+            Some(sprite) => sprite.clone(),
+            None => {
+                log::warn!("Cannot find sprite {}", item_sprite(&definition));
+                return;
+            }
+        };
+
+        commands
+            .spawn(TileComponents {
+                name: (*name).clone(),
+                location: Location(
+                    translation.x(),
+                    translation.y(),
+                    translation.z(),
+                    WorldLocation::World,
+                ),
+                sprite: sprite.clone(),
+                interaction: InteractionKey::new(keys::ITEM_PICKUP),
+                ..Default::default()
+            })
+            .with_bundle(sprite.to_components(translation, scale))
+            .with_bundle(Interactable::new(InteractableType::Item))
+            .with_bundle((
+                (*definition).clone(),
+                *weight,
+                (*item_type).clone(),
+                (*item_slot).clone(),
+                *handle,
+            ));
+    }
+}
+
+/// Level Change System
+/// Takes entities up or down the labyrinth, nothing goes higher than the
+/// ground floor.
+pub fn level_change_system(
+    mut state: Local<LevelChangeState>,
+    level_changes: Res<Events<LevelChangeEvent>>,
+    query: Query<(&mut Level, &mut Location)>,
+) {
+    for event in state.level_changes.iter(&level_changes) {
+        let (mut level, mut location) = match (
+            query.get_mut::<Level>(event.entity),
+            query.get_mut::<Location>(event.entity),
+        ) {
+            (Ok(level), Ok(location)) => (level, location),
+            _ => {
+                log::warn!("{:?} has no level to change", event.entity);
+                continue;
+            }
+        };
+
+        level.0 = (level.0 + event.levels).max(0);
+        location.3 = level.world_location();
+    }
+}
+
+/// Save System
+/// Writes or restores the world whenever a SaveWorldEvent is sent. This is a thread
+/// local system so that the whole world is captured / replaced in one go.
+pub fn save_world_system(world: &mut World, resources: &mut Resources) {
+    let events: Vec<SaveWorldEvent> = {
+        let mut state = resources.get_mut::<SaveState>().unwrap();
+        let save_events = resources.get::<Events<SaveWorldEvent>>().unwrap();
+
+        state.save_events.iter(&save_events).cloned().collect()
+    };
+
+    for event in events {
+        let (result, done) = match &event {
+            SaveWorldEvent::Save(slot) => (save_world(world, resources, slot), "Game saved."),
+            SaveWorldEvent::Load(slot) => (load_world(world, resources, slot), "Game loaded."),
+        };
+
+        let text = match result {
+            Ok(_) => done.to_string(),
+            Err(err) => {
+                log::error!("{}", err);
+                "Something went wrong with the save, see the log for details.".to_string()
+            }
         };
+
+        resources
+            .get_mut::<Events<TextChangeEvent>>()
+            .unwrap()
+            .send(TextChangeEvent {
+                text: text,
+                name: "main".to_string(),
+            });
+    }
+}
+
+/// F5 saves to the current slot, F9 loads it again
+pub fn save_keyboard_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    slots: Res<SaveSlots>,
+    mut save_events: ResMut<Events<SaveWorldEvent>>,
+) {
+    let slot = slots
+        .current
+        .clone()
+        .unwrap_or_else(|| QUICKSAVE_SLOT.to_string());
+
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        save_events.send(SaveWorldEvent::Save(slot.clone()));
+    }
+    if keyboard_input.just_pressed(KeyCode::F9) {
+        save_events.send(SaveWorldEvent::Load(slot));
     }
 }
 
-// TODO Re-implement
-pub fn save_world_system(_world: &mut World, _resources: &mut Resources) {}
+pub fn play_time_system(time: Res<Time>, mut play_time: ResMut<PlayTime>) {
+    play_time.seconds += time.delta_seconds as f64;
+}
 
 pub fn zoom_system(
     windows: ResMut<Windows>,
@@ -307,3 +1014,146 @@ pub fn add_text_to_adventure_log(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::Schedule;
+
+    #[test]
+    fn level_changes_move_entities_through_the_labyrinth() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+
+        resources.insert(Events::<LevelChangeEvent>::default());
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", level_change_system.system());
+        schedule.initialize(&mut world, &mut resources);
+
+        let player = world.spawn(PlayerComponents::new("Brewer"));
+
+        let mut change_level = |levels| {
+            resources
+                .get_mut::<Events<LevelChangeEvent>>()
+                .unwrap()
+                .send(LevelChangeEvent {
+                    entity: player,
+                    levels,
+                });
+            schedule.run(&mut world, &mut resources);
+
+            (
+                *world.get::<Level>(player).unwrap(),
+                world.get::<Location>(player).unwrap().3,
+            )
+        };
+
+        assert_eq!(change_level(2), (Level(2), WorldLocation::Labyrinth));
+        assert_eq!(change_level(-1), (Level(1), WorldLocation::Labyrinth));
+        assert_eq!(change_level(-5), (Level(0), WorldLocation::World));
+    }
+
+    #[test]
+    fn state_changes_are_sent_from_the_first_change_on() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+
+        resources.insert(Events::<StateChangeEvent>::default());
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", state_change_system.system());
+        schedule.initialize(&mut world, &mut resources);
+
+        let mut state = ObjectState::default();
+        state.set_bool("open", false);
+
+        let door = world.spawn((state,));
+
+        let changes = |resources: &Resources| {
+            let events = resources.get::<Events<StateChangeEvent>>().unwrap();
+
+            events
+                .get_reader()
+                .iter(&events)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        // what the door was placed with
+        schedule.run(&mut world, &mut resources);
+        assert!(changes(&resources).is_empty());
+
+        world.clear_trackers();
+        world
+            .get_mut::<ObjectState>(door)
+            .unwrap()
+            .set_bool("open", true);
+        schedule.run(&mut world, &mut resources);
+
+        assert_eq!(
+            changes(&resources),
+            vec![StateChangeEvent {
+                entity: door,
+                key: "open".to_string(),
+                value: Some(StateValue::Bool(true)),
+            }]
+        );
+    }
+
+    #[test]
+    fn npcs_take_their_turns_when_the_player_steps_or_acts() {
+        let mut world = World::new();
+        let mut resources = Resources::default();
+        let mut schedule = Schedule::default();
+
+        resources.insert(WorldSettings {
+            turn_mode: true,
+            ..Default::default()
+        });
+        resources.insert(Turns::default());
+        resources.insert(Events::<PlayerActionEvent>::default());
+        resources.insert(Events::<TurnEvent>::default());
+        schedule.add_stage("update");
+        schedule.add_system_to_stage("update", turn_system.system());
+        schedule.initialize(&mut world, &mut resources);
+
+        let player = world.spawn(PlayerComponents::new("Brewer"));
+        // twice as fast as the player
+        let npc = world.spawn((NonPlayer, Speed(20)));
+
+        let turns = |resources: &Resources| {
+            let events = resources.get::<Events<TurnEvent>>().unwrap();
+
+            events.get_reader().iter(&events).count()
+        };
+
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(turns(&resources), 0);
+        assert!(resources.get::<Turns>().unwrap().contains(npc));
+
+        let step = GridStep::new(Vec3::zero(), Vec3::new(16., 0., 0.), 0.2);
+        world.insert_one(player, step).unwrap();
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(turns(&resources), 2);
+
+        world.clear_trackers();
+        resources
+            .get_mut::<Events<PlayerActionEvent>>()
+            .unwrap()
+            .send(PlayerActionEvent {
+                entity: player,
+                action: actions::USE.to_string(),
+            });
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(turns(&resources), 4);
+
+        // outside of turn mode npcs go by their timers
+        resources.get_mut::<WorldSettings>().unwrap().turn_mode = false;
+        world.clear_trackers();
+        world.remove_one::<GridStep>(player).unwrap();
+        world.insert_one(player, step).unwrap();
+        schedule.run(&mut world, &mut resources);
+        assert_eq!(turns(&resources), 4);
+    }
+}
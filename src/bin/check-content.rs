@@ -0,0 +1,31 @@
+/// Checks the item, mob and recipe files without starting the game
+///
+/// Run it from the root of the repository with
+/// `cargo run --bin check-content`, it lists every problem it finds and exits
+/// with an error when there is one.
+use lab_builder::mobs::MOBS_PATH;
+use lab_core::ITEMS_PATH;
+use lab_data::{ContentSet, RECIPES_PATH};
+use std::process;
+
+fn main() {
+    let (content, mut errors) = ContentSet::load(ITEMS_PATH, MOBS_PATH, RECIPES_PATH);
+
+    errors.extend(content.validate(&lab_sprites::sprite_names()));
+
+    for err in errors.iter() {
+        println!("{}", err);
+    }
+
+    println!(
+        "Checked {} items, {} mobs and {} recipes, found {} problems",
+        content.items.len(),
+        content.mobs.len(),
+        content.recipes.len(),
+        errors.len()
+    );
+
+    if !errors.is_empty() {
+        process::exit(1);
+    }
+}
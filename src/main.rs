@@ -6,7 +6,7 @@ use lab_core::*;
 use lab_entities::prelude::*;
 use lab_input::*;
 use lab_sprites::*;
-use lab_world::{TextChangeEvent, TileInteraction};
+use lab_world::TextChangeEvent;
 
 pub mod layers {
     // z indexes of sprites
@@ -36,7 +36,9 @@ fn main() {
         .add_startup_system_to_stage(stages::POST_INIT, setup.system())
         //.add_plugin(lab_demo::DemoPlugin)
         .add_plugin(lab_tiled_demo::TiledDemoPlugin)
+        .init_resource::<state::StartMenu>()
         .add_system(state::state_transition.system())
+        .add_system(state::start_menu_system.system())
         .run();
 }
 
@@ -77,7 +79,12 @@ fn setup(
         .spawn(PlayerComponents::new("Adam"))
         .with_bundle(player_sprite.to_components(Vec3::new(-64., -64., layers::PLAYER), 2.))
         .with_bundle(Interactable::new(InteractableType::Player))
-        .with(WorldHandle::<TileInteraction>::default())
+        .with(InteractionKey::default())
+        .with(
+            player_sprite
+                .collider()
+                .with_insets(Vec2::new(lab_world::settings::MOVER_INSET, 0.)),
+        )
         .with(MoveAnimation {
             up: walk_right[3..6].to_vec(),
             down: walk_left[0..4].to_vec(),
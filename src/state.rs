@@ -2,6 +2,7 @@
 use bevy::prelude::*;
 use lab_input::prelude::*;
 use lab_sprites::*;
+use lab_world::{SaveSlots, SaveWorldEvent, TextChangeEvent};
 
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
@@ -34,6 +35,158 @@ pub struct SceneState {
     pub next_state: StateType,
 }
 
+/// What the start menu can offer, depending on the saves that exist
+#[derive(Clone, Debug, PartialEq)]
+pub enum StartOption {
+    /// Pick up the most recently played slot
+    Continue(String),
+    /// Choose one of the saved slots
+    Load(Vec<String>),
+    /// One of the saved slots, offered once Load is chosen
+    Slot(String),
+    New,
+}
+
+impl StartOption {
+    pub fn label(&self) -> String {
+        match self {
+            StartOption::Continue(slot) => format!("Continue ({})", slot),
+            StartOption::Load(_) => "Load".to_string(),
+            StartOption::Slot(slot) => slot.clone(),
+            StartOption::New => "New".to_string(),
+        }
+    }
+}
+
+pub fn start_options(slots: &mut SaveSlots) -> Vec<StartOption> {
+    let saved = slots.list().unwrap_or_else(|err| {
+        log::error!("Cannot list save slots: {}", err);
+        Vec::new()
+    });
+
+    let mut options = Vec::new();
+
+    if let Some(latest) = saved.first() {
+        options.push(StartOption::Continue(latest.slot.clone()));
+        options.push(StartOption::Load(
+            saved.iter().map(|m| m.slot.clone()).collect(),
+        ));
+    }
+
+    options.push(StartOption::New);
+
+    options
+}
+
+/// The start menu, it is closed while it has no options
+#[derive(Default, Debug)]
+pub struct StartMenu {
+    pub options: Vec<StartOption>,
+    pub selected: usize,
+}
+
+impl StartMenu {
+    pub fn new(options: Vec<StartOption>) -> StartMenu {
+        StartMenu {
+            options,
+            selected: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.options.is_empty()
+    }
+
+    /// Every option on a line of its own, the selected one marked
+    pub fn text(&self) -> String {
+        self.options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                let marker = if i == self.selected { "> " } else { "  " };
+                format!("{}{}", marker, option.label())
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn show(&self, text_change: &mut Events<TextChangeEvent>) {
+        text_change.send(TextChangeEvent {
+            text: self.text(),
+            name: "main".to_string(),
+        });
+    }
+
+    pub fn select_next(&mut self) {
+        if self.is_open() {
+            self.selected = (self.selected + 1) % self.options.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if self.is_open() {
+            self.selected = (self.selected + self.options.len() - 1) % self.options.len();
+        }
+    }
+
+    pub fn close(&mut self) {
+        *self = StartMenu::default();
+    }
+}
+
+/// The first free slot name for a new game
+fn new_slot(slots: &mut SaveSlots) -> String {
+    (1..)
+        .map(|n| format!("game {}", n))
+        .find(|slot| !slots.exists(slot).unwrap_or(false))
+        .unwrap()
+}
+
+/// Up and down move through the start menu, enter picks the selected option
+pub fn start_menu_system(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut start_menu: ResMut<StartMenu>,
+    mut slots: ResMut<SaveSlots>,
+    mut save_events: ResMut<Events<SaveWorldEvent>>,
+    mut text_change: ResMut<Events<TextChangeEvent>>,
+) {
+    if !start_menu.is_open() {
+        return;
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Down) {
+        start_menu.select_next();
+        start_menu.show(&mut text_change);
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        start_menu.select_previous();
+        start_menu.show(&mut text_change);
+    }
+    if !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    match start_menu.options[start_menu.selected].clone() {
+        StartOption::Continue(slot) | StartOption::Slot(slot) => {
+            save_events.send(SaveWorldEvent::Load(slot));
+            start_menu.close();
+        }
+        StartOption::Load(saved) => {
+            *start_menu = StartMenu::new(saved.into_iter().map(StartOption::Slot).collect());
+            start_menu.show(&mut text_change);
+        }
+        StartOption::New => {
+            let slot = new_slot(&mut slots);
+
+            match slots.create(&slot) {
+                Ok(_) => slots.current = Some(slot),
+                Err(err) => log::error!("Cannot create save slot {}: {}", slot, err),
+            }
+            start_menu.close();
+        }
+    }
+}
+
 #[allow(dead_code, unused_variables, unused_mut)]
 pub fn state_transition(
     mut commands: Commands,
@@ -41,13 +194,19 @@ pub fn state_transition(
     sprite_library: Res<SpriteLibrary>,
     asset_server: Res<AssetServer>,
     mut fonts: ResMut<Assets<Font>>,
+    mut slots: ResMut<SaveSlots>,
+    mut start_menu: ResMut<StartMenu>,
+    mut text_change: ResMut<Events<TextChangeEvent>>,
     mut query: Query<(Changed<SceneState>,)>,
 ) {
     for (state,) in &mut query.iter() {
         match &state.next_state {
             StateType::Init => {}
             #[allow(unused_variables)]
-            StateType::Menu(menu) => {}
+            StateType::Menu(menu) => {
+                *start_menu = StartMenu::new(start_options(&mut slots));
+                start_menu.show(&mut text_change);
+            }
             StateType::CharacterScreen => {}
             StateType::Map => {}
             StateType::Combat => {}